@@ -0,0 +1,146 @@
+use crate::error::SeekerError;
+use crate::gitlab::archive_dir;
+use crate::simulation::Workspace;
+use crate::summary::SeedOutcome;
+use crate::{Monitoring, dispatch};
+use flate2::Compression;
+use serde::Serialize;
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+/// Timing statistics of the harness, measured while dispatching a no-op runner command
+#[derive(Debug, Serialize)]
+pub struct BenchStats {
+    /// Number of seeds dispatched
+    seeds: u32,
+    /// Number of seeds run in parallel
+    chunk_size: usize,
+    /// Whether seed workspaces were prepared inside the job rather than ahead of it
+    prepare_inline: bool,
+    /// Wall-clock duration of the whole dispatch
+    wall_secs: f64,
+    /// Mean time spent preparing the workspace of a seed
+    mean_prepare_secs: f64,
+    /// Mean time spent running the runner command for a seed
+    mean_runner_secs: f64,
+    /// Time per seed spent in the harness rather than in the runner command
+    overhead_per_seed_secs: f64,
+    /// Mean number of runner commands running at the same time
+    achieved_parallelism: f64,
+    /// Duration of archiving the benchmarked directory with single-threaded compression
+    #[serde(skip_serializing_if = "Option::is_none")]
+    archive_secs: Option<f64>,
+    /// Duration of archiving the benchmarked directory with parallel compression
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parallel_archive_secs: Option<f64>,
+}
+
+impl BenchStats {
+    /// Time archiving `dir` with single-threaded then parallel compression
+    pub fn bench_compression(&mut self, dir: &Path) -> Result<(), SeekerError> {
+        let tempdir = tempfile::tempdir()?;
+        let tar_path = tempdir.path().join("bench.tar.gz");
+
+        let started = Instant::now();
+        archive_dir(dir, &tar_path, false, Compression::default())?;
+        self.archive_secs = Some(started.elapsed().as_secs_f64());
+
+        let started = Instant::now();
+        archive_dir(dir, &tar_path, true, Compression::default())?;
+        self.parallel_archive_secs = Some(started.elapsed().as_secs_f64());
+
+        Ok(())
+    }
+}
+
+/// Dispatch `seeds` seeds running `runner_command` through the same machinery as a real run
+///
+/// With `prepare_inline`, each workspace is prepared by the job itself instead of ahead of it,
+/// which gives the baseline to compare the overlapped setup with.
+pub fn bench(
+    seeds: u32,
+    chunk_size: usize,
+    runner_command: &str,
+    prepare_inline: bool,
+) -> Result<BenchStats, SeekerError> {
+    let runner_nanos = Arc::new(AtomicU64::new(0));
+    let job_runner_nanos = Arc::clone(&runner_nanos);
+    let prepare_nanos = Arc::new(AtomicU64::new(0));
+    let job_prepare_nanos = Arc::clone(&prepare_nanos);
+    let runner_command = runner_command.to_string();
+    let mut failures = 0usize;
+
+    let start = Instant::now();
+
+    dispatch(
+        0..seeds,
+        chunk_size,
+        &Monitoring::default(),
+        || {
+            if prepare_inline {
+                return Ok(None);
+            }
+            let started = Instant::now();
+            let workspace = Workspace::prepare()?;
+            prepare_nanos.fetch_add(started.elapsed().as_nanos() as u64, Ordering::Relaxed);
+            Ok(Some(workspace))
+        },
+        move |_seed, workspace: Option<Workspace>| {
+            let _workspace = match workspace {
+                Some(workspace) => workspace,
+                None => {
+                    let started = Instant::now();
+                    let Ok(workspace) = Workspace::prepare() else {
+                        return SeedOutcome::Error;
+                    };
+                    job_prepare_nanos
+                        .fetch_add(started.elapsed().as_nanos() as u64, Ordering::Relaxed);
+                    workspace
+                }
+            };
+            let started = Instant::now();
+            let status = std::process::Command::new("sh")
+                .arg("-c")
+                .arg(&runner_command)
+                .status();
+            job_runner_nanos.fetch_add(started.elapsed().as_nanos() as u64, Ordering::Relaxed);
+            match status {
+                Ok(status) if status.success() => SeedOutcome::Ok,
+                _ => SeedOutcome::Error,
+            }
+        },
+        |_seed, outcome, _duration| {
+            if outcome == SeedOutcome::Error {
+                failures += 1;
+            }
+            Ok(ControlFlow::Continue(()))
+        },
+    )?;
+
+    let wall_secs = start.elapsed().as_secs_f64();
+
+    if failures > 0 {
+        return Err(SeekerError::Other(
+            format!("Runner command failed for {failures} seeds").into(),
+        ));
+    }
+
+    let runner_secs = runner_nanos.load(Ordering::Relaxed) as f64 / 1e9;
+    let prepare_secs = prepare_nanos.load(Ordering::Relaxed) as f64 / 1e9;
+
+    Ok(BenchStats {
+        seeds,
+        chunk_size,
+        prepare_inline,
+        wall_secs,
+        mean_prepare_secs: prepare_secs / seeds as f64,
+        mean_runner_secs: runner_secs / seeds as f64,
+        overhead_per_seed_secs: (wall_secs - runner_secs / chunk_size as f64) / seeds as f64,
+        achieved_parallelism: runner_secs / wall_secs,
+        archive_secs: None,
+        parallel_archive_secs: None,
+    })
+}
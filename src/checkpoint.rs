@@ -0,0 +1,122 @@
+use crate::error::SeekerError;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Minimum delay between two writes of the checkpoint file
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Seeds completed by the interrupted and current runs of a sweep, persisted to resume it
+///
+/// The file holds one seed per line and is rewritten atomically, so that a crash never leaves a
+/// partial checkpoint behind.
+#[derive(Debug)]
+pub struct Checkpoint {
+    path: PathBuf,
+    completed: BTreeSet<u32>,
+    saved_at: Instant,
+    dirty: bool,
+}
+
+impl Checkpoint {
+    /// Load the checkpoint at `path`, starting empty if the file does not exist yet
+    pub fn load(path: &Path) -> Result<Self, SeekerError> {
+        let completed = match std::fs::read_to_string(path) {
+            Ok(content) => content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(|line| {
+                    line.parse::<u32>().map_err(|e| {
+                        SeekerError::SeedParse(format!(
+                            "Invalid seed `{line}` in checkpoint {}: {e}",
+                            path.display()
+                        ))
+                    })
+                })
+                .collect::<Result<BTreeSet<_>, _>>()?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeSet::new(),
+            Err(e) => {
+                return Err(SeekerError::Io(std::io::Error::new(
+                    e.kind(),
+                    format!("Failed to read checkpoint {}: {e}", path.display()),
+                )));
+            }
+        };
+        if !completed.is_empty() {
+            info!(
+                seeds = completed.len(),
+                path = %path.display(),
+                "Resuming from checkpoint, skipping the completed seeds"
+            );
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            completed,
+            saved_at: Instant::now(),
+            dirty: false,
+        })
+    }
+
+    /// Seeds completed so far
+    pub fn completed(&self) -> &BTreeSet<u32> {
+        &self.completed
+    }
+
+    /// Record `seed` as completed, saving the checkpoint if it was not saved recently
+    pub fn record(&mut self, seed: u32) {
+        self.dirty |= self.completed.insert(seed);
+        if self.saved_at.elapsed() >= SAVE_INTERVAL {
+            self.save();
+        }
+    }
+
+    /// Write the checkpoint file if seeds were completed since the last write
+    pub fn save(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        let content: String = self
+            .completed
+            .iter()
+            .map(|seed| format!("{seed}\n"))
+            .collect();
+        let mut tmp_file = self.path.as_os_str().to_owned();
+        tmp_file.push(".tmp");
+        match std::fs::write(&tmp_file, content)
+            .and_then(|_| std::fs::rename(&tmp_file, &self.path))
+        {
+            Ok(()) => self.dirty = false,
+            Err(e) => {
+                warn!(error = ?e, path = %self.path.display(), "Failed to write the checkpoint")
+            }
+        }
+        self.saved_at = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint");
+
+        let mut checkpoint = Checkpoint::load(&path).unwrap();
+        assert!(checkpoint.completed().is_empty());
+        checkpoint.record(3);
+        checkpoint.record(1);
+        checkpoint.save();
+
+        let checkpoint = Checkpoint::load(&path).unwrap();
+        assert_eq!(
+            checkpoint.completed().iter().copied().collect::<Vec<_>>(),
+            [1, 3]
+        );
+        assert!(!dir.path().join("checkpoint.tmp").exists());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::error::SeekerError;
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Config file read from the current directory when `--config` is not given
+pub const DEFAULT_CONFIG_FILE: &str = "seeker.toml";
+
+/// Parse the `key = value` pairs of a config file
+///
+/// Keys are the long flags, with `-` or `_`. Options have no section, tables are rejected.
+pub fn parse(text: &str) -> Result<toml::Table, String> {
+    let options: toml::Table = toml::from_str(text).map_err(|e| e.to_string())?;
+    match options.iter().find(|(_, value)| value.is_table()) {
+        Some((key, _)) => Err(format!("`{key}`: tables are not supported")),
+        None => Ok(options),
+    }
+}
+
+/// Config file of the run: `--config`, else `seeker.toml` if the current directory has one
+fn config_path(matches: &clap::ArgMatches) -> Option<PathBuf> {
+    match matches.get_one::<PathBuf>("config") {
+        Some(path) => Some(path.clone()),
+        None => Path::new(DEFAULT_CONFIG_FILE)
+            .is_file()
+            .then(|| PathBuf::from(DEFAULT_CONFIG_FILE)),
+    }
+}
+
+/// Insert the options of the config file of the run before the command line `args`
+///
+/// Options given on the command line or in the environment take precedence, their config file
+/// values are dropped. Returns the arguments to parse and the ids of the options set by the
+/// config file.
+pub fn with_config_args(
+    command: &clap::Command,
+    args: Vec<OsString>,
+) -> Result<(Vec<OsString>, HashSet<String>), SeekerError> {
+    // Required options may be missing from the command line, set by the config file itself
+    let Ok(matches) = command
+        .clone()
+        .ignore_errors(true)
+        .try_get_matches_from(&args)
+    else {
+        // `--help` and `--version`, left to the actual parsing
+        return Ok((args, HashSet::new()));
+    };
+    let Some(path) = config_path(&matches) else {
+        return Ok((args, HashSet::new()));
+    };
+    let text = std::fs::read_to_string(&path).map_err(|e| {
+        SeekerError::Config(format!("Cannot read config file {}: {e}", path.display()))
+    })?;
+    let options = parse(&text)
+        .map_err(|e| SeekerError::Config(format!("Invalid config file {}: {e}", path.display())))?;
+
+    let mut config_args = Vec::new();
+    let mut config_ids = HashSet::new();
+    for (key, value) in options {
+        let long = key.replace('_', "-");
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(long.as_str()) && long != "config")
+            .ok_or_else(|| {
+                SeekerError::Config(format!(
+                    "Unknown option `{key}` in config file {}",
+                    path.display()
+                ))
+            })?;
+        let id = arg.get_id().as_str();
+        if matches!(
+            matches.value_source(id),
+            Some(clap::parser::ValueSource::CommandLine | clap::parser::ValueSource::EnvVariable)
+        ) {
+            continue;
+        }
+
+        let values = match value {
+            toml::Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                toml::Value::Boolean(value) if !arg.get_action().takes_values() => {
+                    if value {
+                        config_args.push(format!("--{long}").into());
+                    }
+                    continue;
+                }
+                toml::Value::String(value) => value,
+                toml::Value::Integer(value) => value.to_string(),
+                toml::Value::Float(value) => value.to_string(),
+                toml::Value::Boolean(value) => value.to_string(),
+                toml::Value::Datetime(value) => value.to_string(),
+                toml::Value::Array(_) | toml::Value::Table(_) => {
+                    return Err(SeekerError::Config(format!(
+                        "Nested value for `{key}` in config file"
+                    )));
+                }
+            };
+            if !arg.get_action().takes_values() {
+                return Err(SeekerError::Config(format!(
+                    "`{key}` is a flag, set it to true or false"
+                )));
+            }
+            config_args.push(format!("--{long}={value}").into());
+        }
+        config_ids.insert(id.to_string());
+    }
+
+    let mut args = args.into_iter();
+    let merged = args
+        .next()
+        .into_iter()
+        .chain(config_args)
+        .chain(args)
+        .collect();
+    Ok((merged, config_ids))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let options = parse(
+            r#"
+# Nightly run
+fdbserver-path = "/usr/sbin/fdbserver"
+test_file = [
+    "tests/fast/CycleTest.toml", # cycle
+    'tests/slow/SwizzledRollbackSideband.toml',
+]
+chunk-size = 1_000
+fail-fast = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            options.get("fdbserver-path"),
+            Some(&toml::Value::String("/usr/sbin/fdbserver".to_string()))
+        );
+        assert_eq!(
+            options.get("test_file"),
+            Some(&toml::Value::Array(vec![
+                toml::Value::String("tests/fast/CycleTest.toml".to_string()),
+                toml::Value::String("tests/slow/SwizzledRollbackSideband.toml".to_string()),
+            ]))
+        );
+        assert_eq!(options.get("chunk-size"), Some(&toml::Value::Integer(1000)));
+        assert_eq!(options.get("fail-fast"), Some(&toml::Value::Boolean(true)));
+        assert_eq!(options.len(), 4);
+
+        assert!(parse("[gitlab]\ntoken = \"x\"").is_err());
+        assert!(parse("seeds = [1, 2\n").is_err());
+        assert!(parse("chunk-size = 1\nchunk-size = 2").is_err());
+        assert!(parse("fdbserver-path = /usr/sbin/fdbserver").is_err());
+    }
+}
@@ -0,0 +1,73 @@
+use thiserror::Error;
+
+/// Failure of a run, telling its cause apart for callers and tests
+#[derive(Debug, Error)]
+pub enum SeekerError {
+    /// Invalid seed in a seed file, or seed outside of the accepted range
+    #[error("{0}")]
+    SeedParse(String),
+    /// fdbserver could not be started
+    #[error("Failed to start fdbserver: {0}")]
+    Spawn(#[from] subprocess::PopenError),
+    /// No seed completed in time, the workers are most likely stuck
+    #[error("{0}")]
+    Timeout(String),
+    /// Request to GitLab (or GitHub) failed
+    #[error("Request failed: {0}")]
+    Gitlab(#[from] reqwest::Error),
+    /// Filesystem or process I/O failed
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    /// Invalid jq expression, or jq failed on the trace logs
+    #[error("{0}")]
+    Jq(String),
+    /// Invalid command line, config file or combination of options
+    #[error("{0}")]
+    Config(String),
+    /// The environment is not ready for the run, e.g. missing fdbserver binary or test file, full
+    /// scratch volume or output path used by another run
+    #[error("{0}")]
+    Preflight(String),
+    /// The run was stopped by a signal before checking every seed
+    #[error("{0}")]
+    Interrupted(String),
+    /// Any other failure
+    #[error("{0}")]
+    Other(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl From<jq_rs::Error> for SeekerError {
+    fn from(e: jq_rs::Error) -> Self {
+        SeekerError::Jq(e.to_string())
+    }
+}
+
+impl From<gzp::GzpError> for SeekerError {
+    fn from(e: gzp::GzpError) -> Self {
+        SeekerError::Io(std::io::Error::other(e.to_string()))
+    }
+}
+
+/// Failures of the libraries without a variant of their own
+macro_rules! other_errors {
+    ($($error:ty),* $(,)?) => {
+        $(
+            impl From<$error> for SeekerError {
+                fn from(e: $error) -> Self {
+                    SeekerError::Other(Box::new(e))
+                }
+            }
+        )*
+    };
+}
+
+other_errors!(
+    clap::Error,
+    regex::Error,
+    serde_json::Error,
+    std::path::StripPrefixError,
+    walkdir::Error,
+    crate::github::GithubBuilderError,
+    crate::gitlab::GitlabBuilderError,
+    crate::gitlab::PayloadBuilderError,
+);
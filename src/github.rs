@@ -0,0 +1,337 @@
+use crate::error::SeekerError;
+use crate::gitlab::{Payload, archive_dir};
+use crate::reporter::{IssueReporter, ReportedIssue, ReportedSeeds, issue_title, render_footer};
+use derive_builder::Builder;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::time::SystemTime;
+use tracing::{trace, warn};
+
+/// GitHub rejects issue bodies over 65536 characters
+const MAX_BODY_LENGTH: usize = 65536;
+
+#[derive(Debug, Builder, Clone)]
+#[builder(setter(into))]
+pub struct Github {
+    token: String,
+    /// Repository the issues are opened in, as `OWNER/REPO`
+    repo: String,
+    /// Base URL of the REST API, to be changed for GitHub Enterprise
+    #[builder(default = "String::from(\"https://api.github.com\")")]
+    api_url: String,
+    /// Base URL of the release asset uploads
+    #[builder(default = "String::from(\"https://uploads.github.com\")")]
+    upload_url: String,
+    /// Release the archives are attached to, they are not uploaded without it
+    #[builder(default)]
+    release_id: Option<u64>,
+    /// Optional text appended to every issue body
+    #[builder(default)]
+    footer: Option<String>,
+    /// Prefix of the names of the uploaded artifacts
+    #[builder(default = "String::from(\"simulation\")")]
+    artifact_prefix: String,
+    /// Compress archives on every available core
+    #[builder(default)]
+    parallel_compression: bool,
+    /// Level of the gzip compression of the uploaded artifacts
+    #[builder(default)]
+    compression: Compression,
+    /// Seeds already reported during the run, shared by the clones handed to the workers
+    #[builder(setter(skip))]
+    reported_seeds: ReportedSeeds,
+    /// HTTP client shared by every request
+    #[builder(default)]
+    client: reqwest::blocking::Client,
+}
+
+impl Github {
+    fn request(&self, method: reqwest::Method, url: &str) -> reqwest::blocking::RequestBuilder {
+        self.client
+            .request(method, url)
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .header("User-Agent", env!("CARGO_PKG_NAME"))
+    }
+
+    /// Create a secret gist holding the non-empty text `files`, returning its URL
+    pub fn create_gist(
+        &self,
+        description: &str,
+        files: &[(String, &str)],
+    ) -> Result<String, SeekerError> {
+        // Gists reject empty files
+        let files: HashMap<&str, serde_json::Value> = files
+            .iter()
+            .filter(|(_, content)| !content.is_empty())
+            .map(|(name, content)| (name.as_str(), serde_json::json!({ "content": content })))
+            .collect();
+        let params = serde_json::json!({
+            "description": description,
+            "public": false,
+            "files": files,
+        });
+
+        let response = self
+            .request(reqwest::Method::POST, &format!("{}/gists", self.api_url))
+            .body(params.to_string())
+            .send()?;
+        trace!(?response, "GitHub create gist response");
+        let text_response = response.error_for_status()?.text()?;
+        Ok(serde_json::from_str::<HtmlUrlResponse>(&text_response)?.html_url)
+    }
+
+    /// Attach the file at `path` to the release as `name`, returning its download URL
+    pub fn upload_release_asset(
+        &self,
+        release_id: u64,
+        name: &str,
+        path: &Path,
+    ) -> Result<String, SeekerError> {
+        let response = self
+            .request(
+                reqwest::Method::POST,
+                &format!(
+                    "{}/repos/{}/releases/{release_id}/assets",
+                    self.upload_url, self.repo
+                ),
+            )
+            .query(&[("name", name)])
+            .header("Content-Type", "application/gzip")
+            .body(std::fs::read(path)?)
+            .send()?;
+        trace!(?response, "GitHub upload release asset response");
+        let text_response = response.error_for_status()?.text()?;
+        Ok(serde_json::from_str::<AssetResponse>(&text_response)?.browser_download_url)
+    }
+
+    /// Archive `dir` and attach it to the release, `None` when no release is configured
+    fn upload_archive(&self, name: &str, dir: &Path) -> Result<Option<String>, SeekerError> {
+        let Some(release_id) = self.release_id else {
+            return Ok(None);
+        };
+        let tempdir = tempfile::tempdir()?;
+        let tar_path = tempdir.path().join(name);
+        archive_dir(dir, &tar_path, self.parallel_compression, self.compression)?;
+        Ok(Some(
+            self.upload_release_asset(release_id, name, &tar_path)?,
+        ))
+    }
+
+    /// Gzip the file at `path` and attach it to the release, `None` when no release is configured
+    fn upload_compressed(&self, name: &str, path: &Path) -> Result<Option<String>, SeekerError> {
+        let Some(release_id) = self.release_id else {
+            return Ok(None);
+        };
+        let tempdir = tempfile::tempdir()?;
+        let gz_path = tempdir.path().join(name);
+        let mut enc = GzEncoder::new(File::create(&gz_path)?, self.compression);
+        std::io::copy(&mut File::open(path)?, &mut enc)?;
+        enc.try_finish()?;
+        Ok(Some(self.upload_release_asset(release_id, name, &gz_path)?))
+    }
+
+    /// Upload the artifacts of a faulty seed and open an issue for it, returning the issue URL
+    ///
+    /// Text artifacts go to a secret gist, archives are attached to the configured release.
+    pub fn create_issue(&self, payload: Payload) -> Result<String, SeekerError> {
+        let seed = payload.seed;
+        let prefix = &self.artifact_prefix;
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        if self.release_id.is_none() {
+            warn!(
+                seed,
+                "No GitHub release configured, the log archives are not uploaded"
+            );
+        }
+
+        let reproduce_name = format!("reproduce_seed_{seed}.sh");
+        let events_name = format!("{prefix}_events_seed_{seed}.json");
+        let layer_errors_name = format!("{prefix}_layer_errors_seed_{seed}.json");
+        let mut gist_files = vec![
+            (
+                format!("{prefix}_stdout_seed_{seed}.txt"),
+                payload.stdout.as_deref().unwrap_or_default(),
+            ),
+            (
+                format!("{prefix}_stderr_seed_{seed}.txt"),
+                payload.stderr.as_deref().unwrap_or_default(),
+            ),
+            (layer_errors_name, payload.filtered_output.as_str()),
+        ];
+        if let Some(attached_output) = &payload.attached_output {
+            gist_files.push((events_name, attached_output));
+        }
+        if let Some(reproduce_script) = &payload.reproduce_script {
+            gist_files.push((reproduce_name, reproduce_script));
+        }
+        let gist_url = self.create_gist(
+            &format!("Seed Seeker artifacts of seed {seed}"),
+            &gist_files,
+        )?;
+
+        let mut links = format!("- Output, layer errors and scripts: [gist]({gist_url})\n");
+        let archives = [
+            (
+                "Full logs",
+                format!("{prefix}_logs_seed_{seed}_{now}.tar.gz"),
+                Some(&payload.logs),
+            ),
+            (
+                "Verbose re-run logs",
+                format!("{prefix}_verbose_logs_seed_{seed}_{now}.tar.gz"),
+                payload.verbose_logs.as_ref(),
+            ),
+            (
+                "Data directory",
+                format!("{prefix}_data_seed_{seed}_{now}.tar.gz"),
+                payload.data_dir.as_ref(),
+            ),
+        ];
+        for (title, name, dir) in archives {
+            if let Some(dir) = dir
+                && let Some(url) = self.upload_archive(&name, dir)?
+            {
+                links.push_str(&format!("- {title}: [{name}]({url})\n"));
+            }
+        }
+        let mut omitted_events = String::new();
+        if let Some(filtered_events) = &payload.filtered_events {
+            let name = format!("{prefix}_filtered_events_seed_{seed}_{now}.json.gz");
+            omitted_events = match self.upload_compressed(&name, filtered_events)? {
+                Some(url) => format!(
+                    "- … {} more events truncated, see the full filtered events: [{name}]({url})\n",
+                    payload.omitted_events
+                ),
+                None => format!(
+                    "- … {} more events truncated, see the full logs\n",
+                    payload.omitted_events
+                ),
+            };
+        }
+        if let Some(core_dump) = &payload.core_dump {
+            let name = format!("{prefix}_core_seed_{seed}_{now}.gz");
+            if let Some(url) = self.upload_compressed(&name, core_dump)? {
+                links.push_str(&format!("- Core dump: [{name}]({url})\n"));
+            }
+        }
+
+        let kind = if payload.timeout_secs.is_some() {
+            "Timed-out Seed"
+        } else {
+            "Faulty Seed"
+        };
+        let mut details = String::new();
+        if payload.consistency_failures.is_some() {
+            details.push_str("> :warning: **Consistency check failure detected**\n\n");
+        }
+        if payload.missing_traces {
+            details.push_str("> :warning: **No trace file was written**, fdbserver logging may be misconfigured (`-L`, `--trace-format`)\n\n");
+        }
+        details.push_str(&format!(
+            "- Commit ID: {}{}\n",
+            payload.commit_id.as_deref().unwrap_or("Non specified"),
+            if payload.dirty_tree {
+                " (with uncommitted changes)"
+            } else {
+                ""
+            }
+        ));
+        for (title, value) in [
+            ("Exit status", payload.exit_status.clone()),
+            ("Confirmation re-runs", payload.confirmation.clone()),
+            (
+                "Timed out after",
+                payload.timeout_secs.map(|secs| format!("{secs}s")),
+            ),
+            ("Test category", payload.test_category.clone()),
+            (
+                "Simulation count",
+                payload.simulation_count.map(|count| count.to_string()),
+            ),
+            ("Divergence", payload.divergence.clone()),
+        ] {
+            if let Some(value) = value {
+                details.push_str(&format!("- {title}: {value}\n"));
+            }
+        }
+        details.push_str(&links);
+        if let Some(timeout_diagnostics) = &payload.timeout_diagnostics {
+            details.push_str(&format!(
+                "- Timeout diagnostics:\n<details>\n\n```\n{timeout_diagnostics}```\n\n</details>\n\n"
+            ));
+        }
+        if let Some(validation_failure) = &payload.validation_failure {
+            details.push_str(&format!(
+                "- Post-seed validation failed:\n<details>\n\n```\n{validation_failure}\n```\n\n</details>\n\n"
+            ));
+        }
+        if payload.skipped_log_lines > 0 {
+            details.push_str(&format!(
+                "- Skipped malformed log lines: {}\n",
+                payload.skipped_log_lines
+            ));
+        }
+        if let Some(table) = &payload.extracted_fields {
+            details.push_str(&format!("- Extracted fields:\n\n{table}\n"));
+        }
+
+        let footer = render_footer(self.footer.as_deref());
+        let layer_errors = format!(
+            "- Layer errors:\n```json\n{}\n```\n{omitted_events}",
+            payload.filtered_output
+        );
+        // The layer errors are in the gist anyway, leave them out of a body that would be too long
+        let body = if details.len() + layer_errors.len() + footer.len() > MAX_BODY_LENGTH {
+            format!("{details}- Layer errors: too long for the issue, see the gist\n{footer}")
+        } else {
+            format!("{details}{layer_errors}{footer}")
+        };
+
+        let params = serde_json::json!({
+            "title": issue_title(kind, seed, payload.test_file.as_deref()),
+            "body": body,
+            "labels": payload.labels,
+        });
+        let response = self
+            .request(
+                reqwest::Method::POST,
+                &format!("{}/repos/{}/issues", self.api_url, self.repo),
+            )
+            .body(params.to_string())
+            .send()?;
+        trace!(?response, "GitHub create issue response");
+        let text_response = response.error_for_status()?.text()?;
+
+        Ok(serde_json::from_str::<HtmlUrlResponse>(&text_response)?.html_url)
+    }
+}
+
+impl IssueReporter for Github {
+    fn claim_report(&self, seed: u32, test_file: &str) -> bool {
+        self.reported_seeds.claim(seed, test_file)
+    }
+
+    fn report(&self, payload: Payload) -> Result<ReportedIssue, SeekerError> {
+        self.create_issue(payload).map(ReportedIssue::Created)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct HtmlUrlResponse {
+    html_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AssetResponse {
+    browser_download_url: String,
+}
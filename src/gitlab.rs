@@ -1,149 +1,1206 @@
+use crate::error::SeekerError;
+use crate::reporter::{
+    IssueReporter, ReportedIssue, ReportedSeeds, is_rejected, is_transient, issue_title,
+    render_footer, retry_after, retry_delay,
+};
 use derive_builder::Builder;
 use flate2::Compression;
 use flate2::write::GzEncoder;
+use gzp::ZWriter;
+use gzp::deflate::Gzip;
+use gzp::par::compress::{ParCompress, ParCompressBuilder};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs::File;
-use std::path::PathBuf;
-use std::time::SystemTime;
-use tracing::trace;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{info, trace, warn};
 
 #[derive(Debug, Builder, Clone)]
 #[builder(setter(into))]
 pub struct Gitlab {
-    endpoint: String,
+    /// Base URL of the instance, see [`parse_endpoint`]
+    endpoint: reqwest::Url,
     token: String,
     project_id: u64,
+    /// Optional text appended to every issue description
+    #[builder(default)]
+    footer: Option<String>,
+    /// Name of the multipart field holding the uploaded file
+    #[builder(default = "String::from(\"file\")")]
+    upload_field_name: String,
+    /// Extra fields added to the multipart form of uploads
+    #[builder(default)]
+    upload_form_fields: Vec<(String, String)>,
+    /// Prefix of the names of the uploaded artifacts
+    #[builder(default = "String::from(\"simulation\")")]
+    artifact_prefix: String,
+    /// Compress archives on every available core
+    #[builder(default)]
+    parallel_compression: bool,
+    /// Level of the gzip compression of the uploaded artifacts
+    #[builder(default)]
+    compression: Compression,
+    /// Number of retries of a failed request
+    #[builder(default = "2")]
+    max_retries: u32,
+    /// Delay before the first retry, doubled on every following one
+    #[builder(default = "Duration::from_secs(1)")]
+    retry_base_delay: Duration,
+    /// Upper bound of the delay between two attempts of a request
+    #[builder(default = "Duration::from_secs(30)")]
+    retry_max_backoff: Duration,
+    /// Project description template the issue reports are rendered into
+    #[builder(default)]
+    issue_template: Option<String>,
+    /// Template of the issue titles, replacing the built-in title
+    #[builder(default)]
+    title_template: Option<String>,
+    /// Template of the issue descriptions, replacing the built-in report
+    #[builder(default)]
+    body_template: Option<String>,
+    /// Length (in characters) above which the report is moved to a snippet linked from the issue
+    #[builder(default = "1_000_000")]
+    description_limit: usize,
+    /// Comment on the open issue of a seed already reported by a previous run instead of opening another
+    #[builder(default)]
+    dedup_issues: bool,
+    /// Users the faulty-seed issues are assigned to
+    #[builder(default)]
+    assignee_ids: Vec<u64>,
+    /// Milestone the faulty-seed issues are filed under
+    #[builder(default)]
+    milestone_id: Option<u64>,
+    /// Size above which archives are not uploaded, the issue noting that they were omitted
+    #[builder(default)]
+    max_upload_bytes: Option<u64>,
+    /// Seeds already reported during the run, shared by the clones handed to the workers
+    #[builder(setter(skip))]
+    reported_seeds: ReportedSeeds,
+    /// HTTP client shared by every request
+    #[builder(default)]
+    client: reqwest::blocking::Client,
+}
+
+/// Artifact of a report, uploaded unless over the upload limit
+pub struct Artifact {
+    /// Description item linking to the artifact, or noting its omission
+    pub item: String,
+    /// URL of the uploaded artifact
+    pub url: Option<String>,
 }
 
 #[derive(Debug, Builder)]
 #[builder(setter(into))]
 pub struct Payload {
-    /// Json files filtered by Layer and Severity
-    filtered_output: String,
+    /// Json files filtered by Layer and Severity, up to `--max-inline-errors` events
+    pub(crate) filtered_output: String,
+    /// number of filtered events left out of `filtered_output`
+    #[builder(default)]
+    pub(crate) omitted_events: usize,
+    /// path to the file holding every filtered event, set when some were left out
+    #[builder(default)]
+    pub(crate) filtered_events: Option<PathBuf>,
+    /// markdown table of the fields extracted from the filtered events if any
+    #[builder(default)]
+    pub(crate) extracted_fields: Option<String>,
+    /// whether the simulation wrote no JSON trace file at all
+    #[builder(default)]
+    pub(crate) missing_traces: bool,
+    /// number of log lines skipped while filtering because they were malformed
+    #[builder(default)]
+    pub(crate) skipped_log_lines: usize,
+    /// Json files filtered by the attach filter, uploaded as a file
+    #[builder(default)]
+    pub(crate) attached_output: Option<String>,
     /// raw stdout output
-    stdout: Option<String>,
+    pub(crate) stdout: Option<String>,
     /// raw stderr output
-    stderr: Option<String>,
+    pub(crate) stderr: Option<String>,
     /// seed used for the test
-    seed: u32,
+    pub(crate) seed: u32,
     /// commit id of the tested workload if any
-    commit_id: Option<String>,
+    pub(crate) commit_id: Option<String>,
+    /// whether the working tree of the auto-detected commit had uncommitted changes
+    #[builder(default)]
+    pub(crate) dirty_tree: bool,
     /// path to the logs folder
-    logs: PathBuf,
+    pub(crate) logs: PathBuf,
+    /// how the outcomes of the two binaries diverged in differential mode
+    #[builder(default)]
+    pub(crate) divergence: Option<String>,
+    /// path to the core dump of the crashed fdbserver if collected
+    #[builder(default)]
+    pub(crate) core_dump: Option<PathBuf>,
+    /// number of simulations run by fdbserver for the seed if set
+    #[builder(default)]
+    pub(crate) simulation_count: Option<u32>,
+    /// how fdbserver exited, e.g. `killed by signal 6 (SIGABRT)`, if it did not time out
+    #[builder(default)]
+    pub(crate) exit_status: Option<String>,
+    /// how many confirmation re-runs reproduced the failure, e.g. `reproduced 2/3`, if any
+    #[builder(default)]
+    pub(crate) confirmation: Option<String>,
+    /// category of the tested workload if any
+    #[builder(default)]
+    pub(crate) test_category: Option<String>,
+    /// file name of the failing test file when several were run
+    #[builder(default)]
+    pub(crate) test_file: Option<String>,
+    /// file name of the failing test file, even when it is the only one run
+    #[builder(default)]
+    pub(crate) test_file_basename: Option<String>,
+    /// labels set on the created issue
+    #[builder(default)]
+    pub(crate) labels: Vec<String>,
+    /// timeout reached by the simulation if it was terminated
+    #[builder(default)]
+    pub(crate) timeout_secs: Option<u64>,
+    /// path to the logs folder of the verbose re-run of the seed if any
+    #[builder(default)]
+    pub(crate) verbose_logs: Option<PathBuf>,
+    /// path to the simfdb data directory if attached
+    #[builder(default)]
+    pub(crate) data_dir: Option<PathBuf>,
+    /// snapshot of the hung simulation taken at timeout if any
+    #[builder(default)]
+    pub(crate) timeout_diagnostics: Option<String>,
+    /// output of the failed post-seed validation command if any
+    #[builder(default)]
+    pub(crate) validation_failure: Option<String>,
+    /// shell script reproducing the simulation if generated
+    #[builder(default)]
+    pub(crate) reproduce_script: Option<String>,
+    /// consistency check failure events if any were found
+    #[builder(default)]
+    pub(crate) consistency_failures: Option<String>,
 }
 
-impl Gitlab {
-    pub fn upload_file(&self, path_buf: PathBuf) -> Result<String, Box<dyn std::error::Error>> {
-        let client = reqwest::blocking::Client::new();
-        let request = client
-            .post(format!(
-                "https://{}/api/v4/projects/{}/uploads",
-                self.endpoint, self.project_id
+/// Parse the base URL of a GitLab instance, such as `http://gitlab.internal:8080`
+///
+/// A bare host like `gitlab.com` is reached over https.
+pub fn parse_endpoint(endpoint: &str) -> Result<reqwest::Url, SeekerError> {
+    let url = if endpoint.contains("://") {
+        reqwest::Url::parse(endpoint)
+    } else {
+        reqwest::Url::parse(&format!("https://{endpoint}"))
+    }
+    .map_err(|e| SeekerError::Config(format!("Invalid GitLab URL {endpoint}: {e}")))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(SeekerError::Config(format!(
+            "Invalid GitLab URL {endpoint}: expected an http or https URL"
+        )));
+    }
+    Ok(url)
+}
+
+/// Build the HTTP client talking to GitLab
+///
+/// `ca_cert` is a PEM file added to the trusted root certificates, for instances behind a
+/// private CA. `insecure` disables certificate verification altogether.
+pub fn build_client(
+    ca_cert: Option<&Path>,
+    insecure: bool,
+) -> Result<reqwest::blocking::Client, SeekerError> {
+    let mut builder = reqwest::blocking::Client::builder();
+    if let Some(ca_cert) = ca_cert {
+        let pem = std::fs::read(ca_cert).map_err(|e| {
+            SeekerError::Config(format!(
+                "Failed to read CA certificate {}: {e}",
+                ca_cert.display()
             ))
-            .multipart(reqwest::blocking::multipart::Form::new().file("file", path_buf)?)
-            .header("PRIVATE-TOKEN", &self.token)
-            .build()?;
+        })?;
+        builder = builder.add_root_certificate(reqwest::Certificate::from_pem(&pem)?);
+    }
+    if insecure {
+        warn!(
+            "TLS certificate verification of GitLab is DISABLED, never use --gitlab-insecure outside of testing"
+        );
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    Ok(builder.build()?)
+}
+
+/// What to do with a label referencing an unset environment variable
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnsetLabelVar {
+    /// Leave the label out of the issue
+    Skip,
+    /// Fail the run
+    Error,
+}
+
+/// Expand the `$NAME` and `${NAME}` environment variable references of a label template
+///
+/// Returns `None` when a referenced variable is unset and `on_unset` is `Skip`. A `$` not
+/// followed by a variable name is kept as is.
+pub fn expand_label(
+    template: &str,
+    on_unset: UnsetLabelVar,
+) -> Result<Option<String>, SeekerError> {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut label = String::new();
+    let mut rest = template;
+
+    while let Some(dollar) = rest.find('$') {
+        label.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        let (name, remaining) = if let Some(braced) = after.strip_prefix('{')
+            && let Some(end) = braced.find('}')
+        {
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            let end = after.find(|c| !is_name_char(c)).unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+
+        if name.is_empty()
+            || name.starts_with(|c: char| c.is_ascii_digit())
+            || !name.chars().all(is_name_char)
+        {
+            label.push('$');
+            rest = after;
+            continue;
+        }
+
+        match std::env::var(name) {
+            Ok(value) => label.push_str(&value),
+            Err(_) if on_unset == UnsetLabelVar::Skip => {
+                warn!(
+                    label = template,
+                    variable = name,
+                    "Environment variable unset, skipping label"
+                );
+                return Ok(None);
+            }
+            Err(e) => {
+                return Err(SeekerError::Config(format!(
+                    "Label `{template}` references `{name}`: {e}"
+                )));
+            }
+        }
+        rest = remaining;
+    }
+    label.push_str(rest);
+
+    Ok(Some(label))
+}
+
+/// Values of the placeholders of the issue title and description templates
+struct TemplateVars<'a> {
+    seed: u32,
+    commit_id: &'a str,
+    test_file: &'a str,
+    stdout_url: &'a str,
+    stderr_url: &'a str,
+    logs_url: &'a str,
+}
+
+/// Substitute the `{seed}`, `{commit_id}`, `{test_file}`, `{stdout_url}`, `{stderr_url}`,
+/// `{logs_url}`, `{filtered}` and `{report}` placeholders of a title or description template
+///
+/// The `{{seed}}` form of GitLab project templates is accepted too. Unknown placeholders are kept
+/// as is, and the values are not themselves scanned for placeholders.
+fn render_template(template: &str, vars: &TemplateVars, filtered: &str, report: &str) -> String {
+    let seed = vars.seed.to_string();
+    let values = [
+        ("seed", seed.as_str()),
+        ("commit_id", vars.commit_id),
+        ("test_file", vars.test_file),
+        ("stdout_url", vars.stdout_url),
+        ("stderr_url", vars.stderr_url),
+        ("logs_url", vars.logs_url),
+        ("filtered", filtered),
+        ("report", report),
+    ];
+    let placeholder = |rest: &str| {
+        values.iter().find_map(|(name, value)| {
+            [format!("{{{{{name}}}}}"), format!("{{{name}}}")]
+                .into_iter()
+                .find(|placeholder| rest.starts_with(placeholder))
+                .map(|placeholder| (placeholder.len(), *value))
+        })
+    };
+
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match placeholder(rest) {
+            Some((length, value)) => {
+                rendered.push_str(value);
+                rest = &rest[length..];
+            }
+            None => {
+                rendered.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Human-readable size of `bytes`, e.g. `2.3 GB`
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// Percent-encode a string used as a single URL path segment
+fn urlencoding_path_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// Write the content of `dir` to the gzipped tarball `tar_path`
+///
+/// With `parallel`, blocks are compressed on every available core; the output is still a
+/// regular gzip stream.
+pub fn archive_dir(
+    dir: &Path,
+    tar_path: &Path,
+    parallel: bool,
+    compression: Compression,
+) -> Result<(), SeekerError> {
+    let tar = File::create(tar_path)?;
+    if parallel {
+        let enc: ParCompress<Gzip> = ParCompressBuilder::new()
+            .compression_level(compression)
+            .from_writer(tar);
+        let mut tar_builder = tar::Builder::new(enc);
+        tar_builder.append_dir_all("", dir)?;
+        tar_builder.into_inner()?.finish()?;
+    } else {
+        let enc = GzEncoder::new(tar, compression);
+        let mut tar_builder = tar::Builder::new(enc);
+        tar_builder.append_dir_all("", dir)?;
+        let mut gzip_encoder = tar_builder.into_inner()?;
+        gzip_encoder.try_finish()?;
+    }
+    Ok(())
+}
+
+impl Gitlab {
+    /// URL of `path` under the project API
+    fn project_url(&self, path: &str) -> String {
+        format!(
+            "{}/api/v4/projects/{}/{path}",
+            self.endpoint.as_str().trim_end_matches('/'),
+            self.project_id
+        )
+    }
 
-        let response = client.execute(request)?;
+    /// Execute the request built by `build`, retrying on connection errors, 429 and 5xx responses
+    ///
+    /// The request is built again for every attempt, as streamed bodies cannot be replayed. Only
+    /// use it for requests that are safe to repeat.
+    fn execute_with_retries(
+        &self,
+        build: impl Fn() -> Result<reqwest::blocking::Request, SeekerError>,
+    ) -> Result<reqwest::blocking::Response, SeekerError> {
+        self.execute_retrying_if(build, is_transient)
+    }
+
+    /// Execute the request built by `build`, retrying the failed attempts matching `retryable`
+    ///
+    /// The delay between attempts is the `Retry-After` of the response when GitLab sets one.
+    fn execute_retrying_if(
+        &self,
+        build: impl Fn() -> Result<reqwest::blocking::Request, SeekerError>,
+        retryable: fn(&reqwest::Result<reqwest::blocking::Response>) -> bool,
+    ) -> Result<reqwest::blocking::Response, SeekerError> {
+        let mut attempt = 0;
+        loop {
+            let result = self.client.execute(build()?);
+            if !retryable(&result) || attempt >= self.max_retries {
+                return Ok(result?);
+            }
+            attempt += 1;
+
+            let delay = result
+                .as_ref()
+                .ok()
+                .and_then(retry_after)
+                .unwrap_or_else(|| {
+                    retry_delay(attempt - 1, self.retry_base_delay, self.retry_max_backoff)
+                });
+            warn!(
+                attempt,
+                status = ?result.as_ref().map(|response| response.status()),
+                delay_ms = delay.as_millis() as u64,
+                "GitLab request failed, retrying"
+            );
+            std::thread::sleep(delay);
+        }
+    }
+
+    pub fn upload_file(&self, path_buf: PathBuf) -> Result<String, SeekerError> {
+        let response = self.execute_with_retries(|| {
+            let mut form = reqwest::blocking::multipart::Form::new()
+                .file(self.upload_field_name.clone(), &path_buf)?;
+            for (key, value) in &self.upload_form_fields {
+                form = form.text(key.clone(), value.clone());
+            }
+            Ok(self
+                .client
+                .post(self.project_url("uploads"))
+                .multipart(form)
+                .header("PRIVATE-TOKEN", &self.token)
+                .build()?)
+        })?;
         let text_response = response.text()?;
         let url = serde_json::from_str::<UploadResponse>(&text_response)?.url;
         Ok(url)
     }
 
-    pub fn upload_from_string(
-        &self,
-        name: &str,
-        string: &String,
-    ) -> Result<String, Box<dyn std::error::Error>> {
+    pub fn upload_from_string(&self, name: &str, string: &str) -> Result<String, SeekerError> {
         let tempdir = tempfile::tempdir()?;
         let path = tempdir.path().join(name);
         std::fs::write(&path, string)?;
         self.upload_file(path)
     }
 
+    /// Upload the archive at `path`, returning the description item linking to it as `link_name`
+    ///
+    /// Archives larger than `max_upload_bytes` would be rejected and fail the whole report, they
+    /// are skipped and the item notes their size instead.
+    fn upload_artifact(
+        &self,
+        label: &str,
+        link_name: &str,
+        path: PathBuf,
+    ) -> Result<Artifact, SeekerError> {
+        let size = path.metadata()?.len();
+        if let Some(limit) = self.max_upload_bytes
+            && size > limit
+        {
+            warn!(
+                artifact = link_name,
+                size, limit, "Artifact larger than the upload limit, not uploading it"
+            );
+            return Ok(Artifact {
+                item: format!(
+                    "- {label}: {link_name} omitted, {} > {} limit\n",
+                    format_bytes(size),
+                    format_bytes(limit)
+                ),
+                url: None,
+            });
+        }
+        let url = self.upload_file(path)?;
+        Ok(Artifact {
+            item: format!("- {label}: [{link_name}]({url})\n"),
+            url: Some(url),
+        })
+    }
+
+    /// Archive the directory at `path` as `name` and upload it, see [`Gitlab::upload_artifact`]
     pub fn upload_file_from_path(
         &self,
+        label: &str,
+        link_name: &str,
         name: &str,
-        path: &PathBuf,
-    ) -> Result<String, Box<dyn std::error::Error>> {
+        path: &Path,
+    ) -> Result<Artifact, SeekerError> {
         let tempdir = tempfile::tempdir()?;
         let tar_path = tempdir.path().join(name);
-        let tar = File::create(&tar_path).unwrap();
-        let enc = GzEncoder::new(tar, Compression::default());
-        let mut tar_builder = tar::Builder::new(enc);
-        tar_builder.append_dir_all("", path)?;
-        let mut gzip_encoder = tar_builder.into_inner().unwrap();
-        gzip_encoder.try_finish()?;
+        archive_dir(path, &tar_path, self.parallel_compression, self.compression)?;
 
-        self.upload_file(tar_path)
+        self.upload_artifact(label, link_name, tar_path)
     }
 
-    pub fn create_issue(&self, payload: Payload) -> Result<(), Box<dyn std::error::Error>> {
-        let client = reqwest::blocking::Client::new();
+    /// Gzip the file at `path` as `name` and upload it, see [`Gitlab::upload_artifact`]
+    pub fn upload_compressed_file(
+        &self,
+        label: &str,
+        link_name: &str,
+        name: &str,
+        path: &PathBuf,
+    ) -> Result<Artifact, SeekerError> {
+        let tempdir = tempfile::tempdir()?;
+        let gz_path = tempdir.path().join(name);
+        let mut enc = GzEncoder::new(File::create(&gz_path)?, self.compression);
+        std::io::copy(&mut File::open(path)?, &mut enc)?;
+        enc.try_finish()?;
+
+        self.upload_artifact(label, link_name, gz_path)
+    }
+
+    /// Upload the artifacts of a faulty seed and file an issue for it, returning the issue URL
+    pub fn create_issue(&self, payload: Payload) -> Result<ReportedIssue, SeekerError> {
         let seed = payload.seed;
+        let prefix = &self.artifact_prefix;
         let now = SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap()
             .as_secs();
 
-        let upload_url_stdout = self.upload_from_string(
-            &format!("simulation_stdout_seed_{seed}_{now}.txt"),
-            &payload.stdout.unwrap_or_default(),
-        )?;
-        let upload_url_stderr = self.upload_from_string(
-            &format!("simulation_stderr_seed_{seed}_{now}.txt"),
-            &payload.stderr.unwrap_or_default(),
-        )?;
-        let upload_url_logs = self.upload_file_from_path(
-            &format!("simulation_logs_seed_{seed}_{now}.tar.gz"),
+        // The output is not captured with `--no-capture-output`, the report then relies on the traces
+        let mut stdout_url = String::new();
+        let mut stderr_url = String::new();
+        let output = match (&payload.stdout, &payload.stderr) {
+            (None, None) => String::new(),
+            (stdout, stderr) => {
+                let upload_url_stdout = self.upload_from_string(
+                    &format!("{prefix}_stdout_seed_{seed}_{now}.txt"),
+                    stdout.as_deref().unwrap_or_default(),
+                )?;
+                let upload_url_stderr = self.upload_from_string(
+                    &format!("{prefix}_stderr_seed_{seed}_{now}.txt"),
+                    stderr.as_deref().unwrap_or_default(),
+                )?;
+                let output = format!(
+                    "- Output: [simulation.out]({upload_url_stdout})\n- Stderr : [simulation.err]({upload_url_stderr})\n"
+                );
+                stdout_url = upload_url_stdout;
+                stderr_url = upload_url_stderr;
+                output
+            }
+        };
+        let logs = self.upload_file_from_path(
+            "Full logs",
+            "logs.tar.gz",
+            &format!("{prefix}_logs_seed_{seed}_{now}.tar.gz"),
             &payload.logs,
         )?;
+        let logs_url = logs.url.unwrap_or_default();
+        let logs = logs.item;
 
-        let commit_id = payload.commit_id.unwrap_or("Non specified".to_string());
-        let filtered_output = payload.filtered_output;
+        let attached_output = match &payload.attached_output {
+            Some(attached_output) => {
+                let url = self.upload_from_string(
+                    &format!("{prefix}_events_seed_{seed}_{now}.json"),
+                    attached_output,
+                )?;
+                format!("- Attached events: [events.json]({url})\n")
+            }
+            None => String::new(),
+        };
 
-        let params = HashMap::from([
-            (
-                "title",
-                format!("Investigate Faulty Seed #{}", payload.seed),
-            ),
-            (
-                "description",
+        let omitted_events = match &payload.filtered_events {
+            Some(filtered_events) => {
+                self.upload_compressed_file(
+                    &format!(
+                        "… {} more events truncated, see the full filtered events",
+                        payload.omitted_events
+                    ),
+                    "filtered_events.json.gz",
+                    &format!("{prefix}_filtered_events_seed_{seed}_{now}.json.gz"),
+                    filtered_events,
+                )?
+                .item
+            }
+            None => String::new(),
+        };
+
+        let verbose_logs = match &payload.verbose_logs {
+            Some(verbose_logs) => {
+                self.upload_file_from_path(
+                    "Verbose re-run logs",
+                    "verbose_logs.tar.gz",
+                    &format!("{prefix}_verbose_logs_seed_{seed}_{now}.tar.gz"),
+                    verbose_logs,
+                )?
+                .item
+            }
+            None => String::new(),
+        };
+
+        let data_dir = match &payload.data_dir {
+            Some(data_dir) => {
+                self.upload_file_from_path(
+                    "Data directory",
+                    "simfdb.tar.gz",
+                    &format!("{prefix}_data_seed_{seed}_{now}.tar.gz"),
+                    data_dir,
+                )?
+                .item
+            }
+            None => String::new(),
+        };
+
+        let reproduce_script = match &payload.reproduce_script {
+            Some(reproduce_script) => {
+                let name = format!("reproduce_seed_{seed}.sh");
+                let url = self.upload_from_string(&name, reproduce_script)?;
+                format!("- Reproduce script: [{name}]({url})\n")
+            }
+            None => String::new(),
+        };
+
+        let core_dump = match &payload.core_dump {
+            Some(core_dump) => {
+                self.upload_compressed_file(
+                    "Core dump",
+                    "core.gz",
+                    &format!("{prefix}_core_seed_{seed}_{now}.gz"),
+                    core_dump,
+                )?
+                .item
+            }
+            None => String::new(),
+        };
+
+        let template_commit_id = payload.commit_id.clone().unwrap_or_default();
+        let template_test_file = payload.test_file_basename.clone().unwrap_or_default();
+        let mut commit_id = payload.commit_id.unwrap_or("Non specified".to_string());
+        if payload.dirty_tree {
+            commit_id.push_str(" (with uncommitted changes)");
+        }
+        // Timeouts are reported separately, they often point at performance bugs rather than crashes
+        let kind = if payload.timeout_secs.is_some() {
+            "Timed-out Seed"
+        } else {
+            "Faulty Seed"
+        };
+        let timed_out = payload
+            .timeout_secs
+            .map(|timeout_secs| format!("- Timed out after: {timeout_secs}s\n"))
+            .unwrap_or_default();
+        let exit_status = payload
+            .exit_status
+            .map(|exit_status| format!("- Exit status: {exit_status}\n"))
+            .unwrap_or_default();
+        let confirmation = payload
+            .confirmation
+            .map(|confirmation| format!("- Confirmation re-runs: {confirmation}\n"))
+            .unwrap_or_default();
+        let mut timeout = timed_out.clone();
+        if let Some(timeout_diagnostics) = &payload.timeout_diagnostics {
+            timeout.push_str(&format!(
+                "- Timeout diagnostics:\n<details>\n\n```\n{timeout_diagnostics}```\n\n</details>\n\n"
+            ));
+        }
+        let test_category = payload
+            .test_category
+            .map(|category| format!("- Test category: {category}\n"))
+            .unwrap_or_default();
+        let simulation_count = payload
+            .simulation_count
+            .map(|count| format!("- Simulation count: {count}\n"))
+            .unwrap_or_default();
+        let divergence = payload
+            .divergence
+            .map(|divergence| format!("- Divergence: {divergence}\n"))
+            .unwrap_or_default();
+        let validation_failure = payload
+            .validation_failure
+            .map(|output| {
+                format!(
+                    "- Post-seed validation failed:\n<details>\n\n```\n{output}\n```\n\n</details>\n\n"
+                )
+            })
+            .unwrap_or_default();
+        let filtered_output = payload.filtered_output;
+        let skipped_log_lines = if payload.skipped_log_lines > 0 {
+            format!(
+                "- Skipped malformed log lines: {}\n",
+                payload.skipped_log_lines
+            )
+        } else {
+            String::new()
+        };
+        let extracted_fields = payload
+            .extracted_fields
+            .map(|table| format!("- Extracted fields:\n\n{table}\n"))
+            .unwrap_or_default();
+        let has_consistency_failures = payload.consistency_failures.is_some();
+        let consistency_failures = payload
+            .consistency_failures
+            .map(|events| {
                 format!(
-                    r#"- Commit ID: {commit_id}
-- Output: [simulation.out]({upload_url_stdout})
-- Stderr : [simulation.err]({upload_url_stderr})
-- Full logs: [logs.tar.gz]({upload_url_logs})
-- Layer errors:
+                    "> :warning: **Consistency check failure detected**\n\n- Consistency check events:\n```json\n{events}\n```\n"
+                )
+            })
+            .unwrap_or_default();
+
+        let missing_traces = if payload.missing_traces {
+            "> :warning: **No trace file was written**, fdbserver logging may be misconfigured (`-L`, `--trace-format`); the layer errors below are empty for that reason\n\n"
+        } else {
+            ""
+        };
+
+        let links = format!(
+            "{output}{logs}{verbose_logs}{data_dir}{attached_output}{reproduce_script}{core_dump}"
+        );
+        let report = format!(
+            r#"{consistency_failures}{missing_traces}- Commit ID: {commit_id}
+{exit_status}{confirmation}{timeout}{test_category}{simulation_count}{divergence}{validation_failure}{links}{skipped_log_lines}{extracted_fields}- Layer errors:
 ```json
 {filtered_output}
 ```
-"#,
+{omitted_events}"#
+        );
+        let vars = TemplateVars {
+            seed,
+            commit_id: &template_commit_id,
+            test_file: &template_test_file,
+            stdout_url: &stdout_url,
+            stderr_url: &stderr_url,
+            logs_url: &logs_url,
+        };
+        let render =
+            |report: &str, filtered: &str| match (&self.body_template, &self.issue_template) {
+                (Some(template), _) => render_template(template, &vars, filtered, report),
+                // The report is appended to a project template without `{report}` so that it is
+                // never lost
+                (None, Some(template)) if !template.contains("{report}") => format!(
+                    "{}\n\n{report}",
+                    render_template(template, &vars, filtered, report)
                 ),
-            ),
-        ]);
+                (None, Some(template)) => render_template(template, &vars, filtered, report),
+                (None, None) => report.to_string(),
+            };
+        let mut description = render(&report, &filtered_output);
 
-        let params = serde_json::to_string(&params)?;
+        // Rather than truncating an oversized report, keep it whole in a snippet
+        let length = description.chars().count();
+        if length > self.description_limit {
+            warn!(
+                seed,
+                length,
+                limit = self.description_limit,
+                "Issue description too long, moving the full report to a snippet"
+            );
+            let snippet_url = self.create_snippet(
+                &format!("{kind} #{seed} report"),
+                &format!("{prefix}_report_seed_{seed}_{now}.md"),
+                &description,
+            )?;
+            let consistency_warning = if has_consistency_failures {
+                "> :warning: **Consistency check failure detected**\n\n"
+            } else {
+                ""
+            };
+            description = render(
+                &format!(
+                    "{consistency_warning}{missing_traces}> The full report is too long for an issue description, see the [report snippet]({snippet_url})\n\n- Commit ID: {commit_id}\n{exit_status}{confirmation}{timed_out}{test_category}{simulation_count}{divergence}{links}- Full report: [report.md]({snippet_url})\n"
+                ),
+                &format!("See the [report snippet]({snippet_url})"),
+            );
+        }
 
-        let request = client
-            .post(format!(
-                "https://{}/api/v4/projects/{}/issues",
-                self.endpoint, self.project_id
-            ))
-            .body(params)
-            .header("PRIVATE-TOKEN", &self.token)
-            .header("Content-Type", "application/json")
-            .build()?;
+        let title = match &self.title_template {
+            Some(template) => render_template(template, &vars, "", ""),
+            None => issue_title(kind, seed, payload.test_file.as_deref()),
+        };
+        let description = format!("{description}{}", self.render_footer());
 
-        let response = client.execute(request)?;
-        trace!(?response, "Gitlab create issue response");
+        if self.dedup_issues
+            && let Some(issue) = self.find_open_issue(&title)?
+        {
+            info!(
+                seed,
+                issue_url = issue.web_url,
+                "Seed already has an open issue, commenting with the new logs"
+            );
+            self.create_issue_note(issue.iid, &description)?;
+            return Ok(ReportedIssue::Commented(issue.web_url));
+        }
+
+        let mut params = serde_json::json!({ "title": title, "description": description });
+
+        if !payload.labels.is_empty() {
+            params["labels"] = payload.labels.join(",").into();
+        }
+        if !self.assignee_ids.is_empty() {
+            params["assignee_ids"] = self.assignee_ids.clone().into();
+        }
+        if let Some(milestone_id) = self.milestone_id {
+            params["milestone_id"] = milestone_id.into();
+        }
+
+        self.post_issue(&params).map(ReportedIssue::Created)
+    }
+
+    /// Create a private project snippet holding `content` in a file named `file_name`, returning its URL
+    pub fn create_snippet(
+        &self,
+        title: &str,
+        file_name: &str,
+        content: &str,
+    ) -> Result<String, SeekerError> {
+        let params = serde_json::json!({
+            "title": title,
+            "visibility": "private",
+            "files": [{ "file_path": file_name, "content": content }],
+        });
+
+        let response = self.execute_retrying_if(
+            || {
+                Ok(self
+                    .client
+                    .post(self.project_url("snippets"))
+                    .body(params.to_string())
+                    .header("PRIVATE-TOKEN", &self.token)
+                    .header("Content-Type", "application/json")
+                    .build()?)
+            },
+            is_rejected,
+        )?;
+        trace!(?response, "Gitlab create snippet response");
+        let text_response = response.error_for_status()?.text()?;
+        let snippet = serde_json::from_str::<SnippetResponse>(&text_response)?;
+
+        Ok(snippet.web_url)
+    }
+
+    /// Use the project issue description template `name` for the reports
+    ///
+    /// The built-in description is kept, with a warning, when the project has no such template.
+    pub fn load_issue_template(&mut self, name: &str) -> Result<(), SeekerError> {
+        let response = self.execute_with_retries(|| {
+            Ok(self
+                .client
+                .get(self.project_url(&format!(
+                    "templates/issues/{}",
+                    urlencoding_path_segment(name)
+                )))
+                .header("PRIVATE-TOKEN", &self.token)
+                .build()?)
+        })?;
+        trace!(?response, "Gitlab issue template response");
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            warn!(
+                template = name,
+                "Issue template not found in the project, using the built-in description"
+            );
+            return Ok(());
+        }
+        let text_response = response.error_for_status()?.text()?;
+        let template = serde_json::from_str::<TemplateResponse>(&text_response)?;
+        self.issue_template = Some(template.content);
 
         Ok(())
     }
+
+    /// File an issue with the given description, returning the issue URL
+    pub fn create_summary_issue(
+        &self,
+        title: &str,
+        description: &str,
+    ) -> Result<String, SeekerError> {
+        let params = serde_json::json!({
+            "title": title,
+            "description": format!("{description}{}", self.render_footer()),
+        });
+        self.post_issue(&params)
+    }
+
+    /// Find the open issue of the project titled exactly `title`
+    ///
+    /// The search matches substrings, so the issue of seed #123 is also returned for seed #12:
+    /// only an exact title match is kept.
+    fn find_open_issue(&self, title: &str) -> Result<Option<ExistingIssue>, SeekerError> {
+        let response = self.execute_with_retries(|| {
+            Ok(self
+                .client
+                .get(self.project_url("issues"))
+                .query(&[("search", title), ("in", "title"), ("state", "opened")])
+                .header("PRIVATE-TOKEN", &self.token)
+                .build()?)
+        })?;
+        trace!(?response, "Gitlab search issues response");
+        let text_response = response.error_for_status()?.text()?;
+        let issues = serde_json::from_str::<Vec<ExistingIssue>>(&text_response)?;
+
+        Ok(issues.into_iter().find(|issue| issue.title == title))
+    }
+
+    /// Comment on the issue `issue_iid` of the project
+    fn create_issue_note(&self, issue_iid: u64, body: &str) -> Result<(), SeekerError> {
+        let params = serde_json::to_string(&HashMap::from([("body", body)]))?;
+
+        let response = self.execute_retrying_if(
+            || {
+                Ok(self
+                    .client
+                    .post(self.project_url(&format!("issues/{issue_iid}/notes")))
+                    .body(params.clone())
+                    .header("PRIVATE-TOKEN", &self.token)
+                    .header("Content-Type", "application/json")
+                    .build()?)
+            },
+            is_rejected,
+        )?;
+        trace!(?response, "Gitlab create issue note response");
+        response.error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Comment on the merge request `merge_request_iid` of the project
+    pub fn create_merge_request_note(
+        &self,
+        merge_request_iid: u64,
+        body: &str,
+    ) -> Result<(), SeekerError> {
+        let params = serde_json::to_string(&HashMap::from([(
+            "body",
+            format!("{body}{}", self.render_footer()),
+        )]))?;
+
+        let response = self.execute_retrying_if(
+            || {
+                Ok(self
+                    .client
+                    .post(self.project_url(&format!("merge_requests/{merge_request_iid}/notes")))
+                    .body(params.clone())
+                    .header("PRIVATE-TOKEN", &self.token)
+                    .header("Content-Type", "application/json")
+                    .build()?)
+            },
+            is_rejected,
+        )?;
+        trace!(?response, "Gitlab create merge request note response");
+        response.error_for_status()?;
+
+        Ok(())
+    }
+
+    fn post_issue(&self, params: &serde_json::Value) -> Result<String, SeekerError> {
+        let params = params.to_string();
+
+        let response = self.execute_retrying_if(
+            || {
+                Ok(self
+                    .client
+                    .post(self.project_url("issues"))
+                    .body(params.clone())
+                    .header("PRIVATE-TOKEN", &self.token)
+                    .header("Content-Type", "application/json")
+                    .build()?)
+            },
+            is_rejected,
+        )?;
+        trace!(?response, "Gitlab create issue response");
+        let text_response = response.error_for_status()?.text()?;
+        let issue = serde_json::from_str::<IssueResponse>(&text_response)?;
+
+        Ok(issue.web_url)
+    }
+
+    /// Render the footer appended to the issue description
+    fn render_footer(&self) -> String {
+        render_footer(self.footer.as_deref())
+    }
+}
+
+impl IssueReporter for Gitlab {
+    fn claim_report(&self, seed: u32, test_file: &str) -> bool {
+        self.reported_seeds.claim(seed, test_file)
+    }
+
+    fn report(&self, payload: Payload) -> Result<ReportedIssue, SeekerError> {
+        self.create_issue(payload)
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct UploadResponse {
     url: String,
 }
+
+#[derive(Debug, Deserialize)]
+struct TemplateResponse {
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SnippetResponse {
+    web_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueResponse {
+    web_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExistingIssue {
+    iid: u64,
+    title: String,
+    web_url: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    const SERVICE_UNAVAILABLE: &str = "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const INTERNAL_SERVER_ERROR: &str =
+        "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}";
+
+    /// Serve `responses` in order, one per connection, returning the server URL and the number
+    /// of requests received once all were served
+    fn serve(responses: Vec<&'static str>) -> (String, std::thread::JoinHandle<usize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = 0;
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buffer = [0; 4096];
+                let _ = stream.read(&mut buffer).unwrap();
+                stream.write_all(response.as_bytes()).unwrap();
+                requests += 1;
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    fn api() -> Gitlab {
+        GitlabBuilder::default()
+            .token("token")
+            .endpoint(parse_endpoint("localhost").unwrap())
+            .project_id(1u64)
+            .retry_base_delay(Duration::from_millis(1))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_render_template() {
+        let vars = TemplateVars {
+            seed: 42,
+            commit_id: "9b1fc0a",
+            test_file: "CycleTest.toml",
+            stdout_url: "/uploads/out",
+            stderr_url: "/uploads/err",
+            logs_url: "/uploads/logs",
+        };
+        assert_eq!(
+            render_template(
+                "[sim] seed {seed} @ {commit_id} in {test_file}",
+                &vars,
+                "",
+                ""
+            ),
+            "[sim] seed 42 @ 9b1fc0a in CycleTest.toml"
+        );
+        assert_eq!(
+            render_template(
+                "/cc @oncall\n[logs]({logs_url}) [err]({stderr_url}) {unknown} {\n{filtered}",
+                &vars,
+                "{seed}",
+                "report"
+            ),
+            "/cc @oncall\n[logs](/uploads/logs) [err](/uploads/err) {unknown} {\n{seed}"
+        );
+        // GitLab project templates use double braces
+        assert_eq!(
+            render_template("Seed {{seed}}\n\n{{report}}", &vars, "", "report"),
+            "Seed 42\n\nreport"
+        );
+    }
+
+    #[test]
+    fn test_expand_label() {
+        // Set by cargo for the test binaries
+        let expand = |template| expand_label(template, UnsetLabelVar::Error).unwrap();
+        assert_eq!(
+            expand("pkg:$CARGO_PKG_NAME").as_deref(),
+            Some("pkg:seed-seeker")
+        );
+        assert_eq!(
+            expand("${CARGO_PKG_NAME}_ci").as_deref(),
+            Some("seed-seeker_ci")
+        );
+        assert_eq!(expand("plain").as_deref(), Some("plain"));
+        // A `$` not followed by a variable name is kept
+        assert_eq!(expand("cost$$").as_deref(), Some("cost$$"));
+        assert_eq!(expand("trailing$").as_deref(), Some("trailing$"));
+        assert_eq!(expand("$1-${CARGO").as_deref(), Some("$1-${CARGO"));
+
+        let unset = "team:$SEED_SEEKER_TEST_UNSET_VARIABLE";
+        assert_eq!(expand_label(unset, UnsetLabelVar::Skip).unwrap(), None);
+        let error = expand_label(unset, UnsetLabelVar::Error)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("SEED_SEEKER_TEST_UNSET_VARIABLE"), "{error}");
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(2_469_606_195), "2.3 GB");
+    }
+
+    #[test]
+    fn test_parse_endpoint() {
+        let api = |endpoint| {
+            GitlabBuilder::default()
+                .token("token")
+                .endpoint(parse_endpoint(endpoint).unwrap())
+                .project_id(1u64)
+                .build()
+                .unwrap()
+        };
+
+        assert_eq!(
+            api("gitlab.com").project_url("issues"),
+            "https://gitlab.com/api/v4/projects/1/issues"
+        );
+        assert_eq!(
+            api("http://gitlab.internal:8080").project_url("issues"),
+            "http://gitlab.internal:8080/api/v4/projects/1/issues"
+        );
+        assert_eq!(
+            api("https://example.com/gitlab/").project_url("issues"),
+            "https://example.com/gitlab/api/v4/projects/1/issues"
+        );
+        assert!(parse_endpoint("ftp://gitlab.com").is_err());
+    }
+
+    #[test]
+    fn test_retry_on_service_unavailable() {
+        let (url, server) = serve(vec![SERVICE_UNAVAILABLE, OK]);
+        let api = api();
+
+        let response = api
+            .execute_with_retries(|| Ok(api.client.post(&url).build()?))
+            .unwrap();
+
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(server.join().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_no_retry_of_creation_on_internal_server_error() {
+        let (url, server) = serve(vec![INTERNAL_SERVER_ERROR]);
+        let api = api();
+
+        let response = api
+            .execute_retrying_if(|| Ok(api.client.post(&url).build()?), is_rejected)
+            .unwrap();
+
+        assert_eq!(
+            response.status(),
+            reqwest::StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(server.join().unwrap(), 1);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::reporter::ReportedIssue;
+use crate::summary::SeedOutcome;
+use std::path::Path;
+use std::time::Duration;
+
+/// Outcome of one seed run against one test file
+#[derive(Debug)]
+struct TestCase {
+    seed: u32,
+    test_file: String,
+    outcome: SeedOutcome,
+    duration: Duration,
+}
+
+/// JUnit XML report of a run, for CI systems showing per-test results
+///
+/// Every checked seed is a test case named after the seed, in a class named after the test file:
+/// faulty and suspicious seeds are failures, timed-out seeds and seeds which could not be
+/// checked are errors.
+#[derive(Debug, Default)]
+pub struct JunitReport {
+    test_cases: Vec<TestCase>,
+}
+
+impl JunitReport {
+    pub fn record(
+        &mut self,
+        seed: u32,
+        test_file: &str,
+        outcome: &SeedOutcome,
+        duration: Duration,
+    ) {
+        self.test_cases.push(TestCase {
+            seed,
+            test_file: test_file.to_string(),
+            outcome: outcome.clone(),
+            duration,
+        });
+    }
+
+    /// Render the report as a single `<testsuite>` document
+    pub fn render(&self) -> String {
+        let count = |is_counted: fn(&SeedOutcome) -> bool| {
+            self.test_cases
+                .iter()
+                .filter(|test_case| is_counted(&test_case.outcome))
+                .count()
+        };
+        let failures = count(|outcome| {
+            matches!(
+                outcome,
+                SeedOutcome::Faulty { .. } | SeedOutcome::Suspicious
+            )
+        });
+        let errors = count(|outcome| matches!(outcome, SeedOutcome::TimedOut | SeedOutcome::Error));
+        let time: f64 = self
+            .test_cases
+            .iter()
+            .map(|test_case| test_case.duration.as_secs_f64())
+            .sum();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuite name=\"{}\" tests=\"{}\" failures=\"{failures}\" errors=\"{errors}\" skipped=\"0\" time=\"{time:.3}\">\n",
+            env!("CARGO_PKG_NAME"),
+            self.test_cases.len()
+        ));
+        for test_case in &self.test_cases {
+            xml.push_str(&format!(
+                "  <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                test_case.seed,
+                escape(&test_case.test_file),
+                test_case.duration.as_secs_f64()
+            ));
+            match &test_case.outcome {
+                SeedOutcome::Ok => xml.push_str("/>\n"),
+                SeedOutcome::Faulty {
+                    issue,
+                    filtered_output,
+                } => {
+                    let message = match issue {
+                        Some(ReportedIssue::Created(url)) => {
+                            format!("Faulty seed, reported in {url}")
+                        }
+                        Some(ReportedIssue::Commented(url)) => {
+                            format!("Faulty seed, commented on {url}")
+                        }
+                        None => "Faulty seed".to_string(),
+                    };
+                    xml.push_str(&format!(
+                        ">\n    <failure type=\"faulty\" message=\"{}\">{}</failure>\n  </testcase>\n",
+                        escape(&message),
+                        escape(filtered_output.as_deref().unwrap_or_default())
+                    ));
+                }
+                SeedOutcome::Suspicious => xml.push_str(
+                    ">\n    <failure type=\"suspicious\" message=\"Failure exit status not reported as faulty\"/>\n  </testcase>\n",
+                ),
+                SeedOutcome::TimedOut => xml.push_str(
+                    ">\n    <error type=\"timeout\" message=\"Simulation timed out\"/>\n  </testcase>\n",
+                ),
+                SeedOutcome::Error => xml.push_str(
+                    ">\n    <error type=\"error\" message=\"Seed could not be checked\"/>\n  </testcase>\n",
+                ),
+            }
+        }
+        xml.push_str("</testsuite>\n");
+        xml
+    }
+
+    /// Write the report to `path`, replacing it atomically
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let mut tmp_file = path.as_os_str().to_owned();
+        tmp_file.push(".tmp");
+        std::fs::write(&tmp_file, self.render())?;
+        std::fs::rename(&tmp_file, path)
+    }
+}
+
+/// Escape `text` for XML content and attribute values
+///
+/// Control characters, which XML 1.0 does not allow even escaped, are dropped.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut report = JunitReport::default();
+        report.record(1, "test.toml", &SeedOutcome::Ok, Duration::from_secs(1));
+        report.record(
+            2,
+            "test.toml",
+            &SeedOutcome::Faulty {
+                issue: None,
+                filtered_output: Some("{\"Type\":\"<Error>\" & more}\u{1b}".to_string()),
+            },
+            Duration::from_secs(2),
+        );
+        report.record(
+            3,
+            "test.toml",
+            &SeedOutcome::TimedOut,
+            Duration::from_secs(3),
+        );
+
+        let xml = report.render();
+        assert!(
+            xml.contains("tests=\"3\" failures=\"1\" errors=\"1\" skipped=\"0\" time=\"6.000\"")
+        );
+        assert!(xml.contains("<testcase name=\"1\" classname=\"test.toml\" time=\"1.000\"/>"));
+        assert!(xml.contains(
+            "<failure type=\"faulty\" message=\"Faulty seed\">{&quot;Type&quot;:&quot;&lt;Error&gt;&quot; &amp; more}</failure>"
+        ));
+        assert!(xml.contains("<error type=\"timeout\""));
+    }
+}
@@ -1,39 +1,177 @@
-use crate::gitlab::{Gitlab, PayloadBuilder};
-use crate::seed::{SeedIterator, merge_user_defined_seeds};
-use clap::Parser;
-use std::io::BufRead;
+use crate::checkpoint::Checkpoint;
+pub use crate::error::SeekerError;
+use crate::gitlab::{Gitlab, PayloadBuilder, UnsetLabelVar, expand_label};
+use crate::junit::JunitReport;
+use crate::logs::{
+    CONSISTENCY_CHECK_FILTER, CONSISTENCY_CHECK_LABEL, extract_fields, filter_logs, filter_logs_to,
+    severity_filter,
+};
+use crate::preflight::DiskSpaceCheck;
+use crate::progress::Progress;
+use crate::redact::Redactor;
+use crate::reporter::{Backend, IssueReporter, ReportedIssue};
+use crate::seed::{
+    Coverage, MAX_SEED, SeedIterator, check_seed_range, is_sampled, merge_user_defined_seeds,
+    parse_seed_range,
+};
+use crate::simulation::{
+    Buggify, ConfirmMode, Simulation, Workspace, describe_divergence, simulate,
+};
+use crate::sink::{OutcomeSink, PrometheusSink, ResultDirSink, StatsdSink};
+pub use crate::summary::RunStatus;
+use crate::summary::{SeedOrder, SeedOutcome, Summary, SummaryTarget};
+use clap::{CommandFactory, FromArgMatches};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{IsTerminal, Write};
+use std::ops::ControlFlow;
+use std::os::unix::fs::PermissionsExt;
 use std::path::PathBuf;
-use std::time::Duration;
-use subprocess::{PopenConfig, Redirection};
+use std::time::{Duration, Instant};
 use tracing::{info, warn};
 
+mod bench;
+mod checkpoint;
+mod config;
+mod error;
+mod github;
 mod gitlab;
+mod junit;
+mod lock;
+mod logs;
+mod preflight;
+mod progress;
+mod redact;
+#[cfg(feature = "async-reporting")]
+mod report_queue;
+mod reporter;
 mod seed;
+mod simulation;
+mod sink;
+mod summary;
+mod test_files;
 
 const DEFAULT_CHUNK_SIZE: usize = 10;
 const DEFAULT_TIMEOUT_SECS: u64 = 120;
+const DEFAULT_FLUSH_WINDOW_SECS: u64 = 5;
+const DEFAULT_DISK_SPACE_PER_SEED_MB: u64 = 512;
+const DEFAULT_SIMULATION_COUNT_FLAG: &str = "--simulation-count";
+const DEFAULT_STATSD_PREFIX: &str = "seed_seeker";
+const DEFAULT_EMBED_FILTER: &str = r#"select(.Layer=="Rust") | select(.Severity=="40")"#;
+const DEFAULT_MAX_INLINE_ERRORS: usize = 100;
+const DEFAULT_UPLOAD_FIELD_NAME: &str = "file";
+const DEFAULT_ARTIFACT_PREFIX: &str = "simulation";
+const DEFAULT_VERIFY_PASSES: u32 = 10;
+const DEFAULT_GITLAB_RETRY_MAX_BACKOFF_SECS: u64 = 30;
+const DEFAULT_GITLAB_MAX_RETRIES: u32 = 2;
+const DEFAULT_GITLAB_RETRY_BASE_DELAY_MS: u64 = 1000;
+/// GitLab rejects descriptions over 1048576 characters, keep a margin for the footer
+const DEFAULT_ISSUE_DESCRIPTION_LIMIT: usize = 1_000_000;
+/// Size above which attaching a data directory is reported as expensive
+const LARGE_DATA_DIR_BYTES: u64 = 100 * 1024 * 1024;
+const DEFAULT_RERUN_VERBOSE_ARGS: [&str; 2] = ["--knob_min_trace_severity", "5"];
 
 fn default_fdbserver_path() -> String {
     String::from("/usr/sbin/fdbserver")
 }
 
+/// Parse a `KEY=VALUE` pair, the value may contain `=`
+fn parse_key_value(pair: &str) -> Result<(String, String), String> {
+    match pair.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got `{pair}`")),
+    }
+}
+
 #[derive(clap::Parser, Debug, Clone)]
+#[clap(subcommand_negates_reqs = true)]
 struct Cli {
+    #[clap(subcommand)]
+    command: Option<Command>,
     /// Path to fdbserver binary
     #[clap(long, default_value_t = default_fdbserver_path())]
     fdbserver_path: String,
-    /// Path to test file to run
-    #[clap(long, short = 'f')]
-    test_file: String,
+    /// Path to a second fdbserver binary for differential testing
+    /// Each seed runs on both binaries and is reported only when their outcomes differ
+    #[clap(long)]
+    fdbserver_path_b: Option<String>,
+    /// Path to test file to run, repeat to run every seed against each test file
+    #[clap(
+        long,
+        short = 'f',
+        required_unless_present_any = ["list_test_files", "explain_config"]
+    )]
+    test_file: Vec<String>,
+    /// Index in `test_file` of the test file run by the simulations
+    #[clap(skip)]
+    test_file_index: usize,
+    /// Directory of test files, walked recursively by `--list-test-files`
+    #[clap(long, requires = "list_test_files")]
+    test_dir: Option<PathBuf>,
+    /// Print the test files discovered in `--test-dir` with their inferred category, then exit
+    #[clap(long, requires = "test_dir")]
+    list_test_files: bool,
+    /// Log the fdbserver command line of every seed instead of running it, after checking the setup
+    #[clap(long)]
+    dry_run: bool,
     /// Max iterations to run
     #[clap(long)]
     max_iterations: Option<u64>,
+    /// Issue tracker the faulty seeds are reported to
+    #[clap(long, value_enum, default_value_t = Backend::Gitlab)]
+    backend: Backend,
+    /// GitHub token to use with `--backend github`
+    #[clap(long, env = "GITHUB_TOKEN", hide_env_values = true)]
+    github_token: Option<String>,
+    /// GitHub repository where to create the issues, as `OWNER/REPO`
+    #[clap(long, env = "GITHUB_REPOSITORY")]
+    github_repo: Option<String>,
+    /// Id of the GitHub release the log archives are attached to, they are not uploaded without it
+    #[clap(long)]
+    github_release_id: Option<u64>,
+    /// GitHub REST API endpoint, to be changed for GitHub Enterprise
+    #[clap(long, default_value = "https://api.github.com")]
+    github_api_url: String,
+    /// GitHub release asset upload endpoint
+    #[clap(long, default_value = "https://uploads.github.com")]
+    github_upload_url: String,
     /// Gitlab token to use
     #[clap(long, env = "GITLAB_TOKEN", hide_env_values = true)]
     token: Option<String>,
-    /// Gitlab endpoint to use
+    /// Gitlab endpoint to use, a bare host or a base URL such as `http://gitlab.internal:8080`
     #[clap(long, env = "GITLAB_URL", default_value = "gitlab.com")]
     gitlab_url: String,
+    /// PEM certificate of the CA signing the GitLab certificate, trusted in addition to the system ones
+    #[clap(long)]
+    gitlab_ca_cert: Option<PathBuf>,
+    /// Do not verify the TLS certificate of GitLab, for testing only
+    #[clap(long)]
+    gitlab_insecure: bool,
+    /// Name of the project issue description template the reports are rendered into
+    #[clap(long)]
+    gitlab_issue_template: Option<String>,
+    /// Title of the GitLab issues, with `{seed}`, `{commit_id}` and `{test_file}` placeholders
+    #[clap(long)]
+    issue_title_template: Option<String>,
+    /// Description of the GitLab issues, with `{seed}`, `{commit_id}`, `{test_file}`,
+    /// `{stdout_url}`, `{stderr_url}`, `{logs_url}`, `{filtered}` and `{report}` placeholders
+    #[clap(long)]
+    issue_body_template: Option<String>,
+    /// Number of retries of a GitLab request failing with a 429, a 5xx or a connection error
+    #[clap(long, default_value_t = DEFAULT_GITLAB_MAX_RETRIES)]
+    gitlab_max_retries: u32,
+    /// Delay (in milliseconds) before the first retry of a failed GitLab request, doubled on every following one
+    #[clap(long, default_value_t = DEFAULT_GITLAB_RETRY_BASE_DELAY_MS)]
+    gitlab_retry_base_delay_ms: u64,
+    /// Maximum delay (in seconds) between two attempts of a failed GitLab upload
+    #[clap(long, default_value_t = DEFAULT_GITLAB_RETRY_MAX_BACKOFF_SECS)]
+    gitlab_retry_max_backoff_secs: u64,
+    /// Comment on the open issue of a seed reported by a previous run instead of opening a duplicate
+    #[clap(long)]
+    dedup_issues: bool,
+    /// Length (in characters) above which the issue report is moved to a snippet linked from the issue
+    #[clap(long, default_value_t = DEFAULT_ISSUE_DESCRIPTION_LIMIT)]
+    issue_description_limit: usize,
     /// Gitlab project id where to create the issue
     /// Optional; required only when a token is provided
     #[clap(long, env = "GITLAB_PROJECT_ID")]
@@ -41,29 +179,531 @@ struct Cli {
     /// Git commit ID
     #[clap(long)]
     commit_id: Option<String>,
-    /// Seed file to use
+    /// Use the HEAD commit of the git repository of the current directory when `--commit-id` is
+    /// not set
+    #[clap(long)]
+    auto_commit_id: bool,
+    /// Whether the tracked files of the auto-detected commit have uncommitted changes
+    #[clap(skip)]
+    dirty_tree: bool,
+    /// Seed file to use, `-` to read the seeds from the standard input
     #[clap(long)]
     seed_file: Option<String>,
     /// Seeds to use
     #[clap(long)]
     seeds: Option<Vec<u32>>,
-    /// Number of seeds to run in parallel
+    /// Drop the repeated seeds of `--seeds` and `--seed-file`, keeping the first occurrence
     #[clap(long)]
+    dedup_seeds: bool,
+    /// Number of random seeds to check, an exact alternative to random seeds with `--max-iterations`
+    #[clap(long, value_name = "N", conflicts_with_all = ["seeds", "seed_file", "seed_range", "loop_seeds"])]
+    random_count: Option<usize>,
+    /// Seed of the random seed generator, the same value producing the same random seeds
+    #[clap(long, value_name = "N")]
+    rng_seed: Option<u64>,
+    /// Never check the same random seed twice in a run, at the cost of remembering every seed drawn
+    #[clap(long)]
+    unique_random: bool,
+    /// Contiguous `START..END` range of seeds (END excluded) checked in ascending order
+    #[clap(long, value_parser = parse_seed_range, conflicts_with_all = ["seeds", "seed_file"])]
+    seed_range: Option<std::ops::Range<u32>>,
+    /// Number of seeds to run in parallel, `auto` or omitted for the number of CPU cores
+    #[clap(long, value_parser = parse_chunk_size)]
     chunk_size: Option<usize>,
     /// Stop the run after the first faulty seed is found
     #[clap(long)]
     fail_fast: bool,
+    /// Stop dispatching seeds once this many faulty seeds were found, the in-flight ones still complete
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    max_faulty_before_abort: Option<u64>,
     /// Timeout (in seconds) to wait for each simulation before terminating it
+    /// A `# timeout: <SECONDS>` directive heading a test file overrides it for that file
     #[clap(long = "timeout-secs", env = "TIMEOUT_SECS", default_value_t = DEFAULT_TIMEOUT_SECS)]
     timeout_secs: u64,
+    /// Timeouts set by the directives of the test files, by index in `test_file`
+    #[clap(skip)]
+    test_file_timeouts: Vec<Option<u64>>,
+    /// Time (in seconds) a timed-out simulation is given to flush its traces after SIGTERM, before SIGKILL
+    #[clap(long, alias = "kill-grace-secs", default_value_t = DEFAULT_FLUSH_WINDOW_SECS)]
+    flush_window_secs: u64,
+    /// Text appended to every created issue description (`\n` starts a new line)
+    #[clap(long, env = "ISSUE_FOOTER")]
+    issue_footer: Option<String>,
+    /// Append every checked seed to this file, one per line
+    #[clap(long)]
+    record_seeds: Option<String>,
+    /// Collect core dumps of simulations killed by a signal and attach them to the report
+    #[clap(long)]
+    collect_core_dumps: bool,
+    /// Only keep trace events emitted by this simulated machine (e.g. `2.0.1.0:1`)
+    #[clap(long)]
+    trace_machine: Option<String>,
+    /// Wait for another run to release the shared output paths instead of failing
+    #[clap(long)]
+    lock_wait: bool,
+    /// Number of simulations fdbserver runs for each seed
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..))]
+    simulation_count: Option<u32>,
+    /// fdbserver flag receiving the simulation count
+    #[clap(long, default_value = DEFAULT_SIMULATION_COUNT_FLAG)]
+    simulation_count_flag: String,
+    /// Buggify mode of the simulations
+    #[clap(long, value_enum, default_value_t = Buggify::On)]
+    buggify: Buggify,
+    /// Extra argument passed to fdbserver before the seed, repeat for several
+    #[clap(long = "fdbserver-arg", allow_hyphen_values = true)]
+    fdbserver_args: Vec<String>,
+    /// Category of the test file (e.g. storage, consistency), added to reports and issue labels
+    #[clap(long)]
+    test_category: Option<String>,
+    /// Label the issues with the name of the test file, to filter them by workload
+    /// Always done when several test files are run
+    #[clap(long)]
+    label_test_file: bool,
+    /// Re-run a faulty seed up to this many times to confirm the failure before reporting it
+    #[clap(long, default_value_t = 0)]
+    confirm_retries: u32,
+    /// Confirmation re-runs which must reproduce the failure for the seed to be reported
+    #[clap(long, value_enum, default_value_t = ConfirmMode::Any)]
+    confirm_mode: ConfirmMode,
+    /// Report timed-out seeds as faulty, with the logs written before the timeout
+    #[clap(long, alias = "treat-timeout-as-failure")]
+    timeout_is_failure: bool,
+    /// jq expression selecting the trace events embedded in the issue description
+    #[clap(long, alias = "log-filter", default_value = DEFAULT_EMBED_FILTER)]
+    embed_filter: String,
+    /// Number of trace events embedded in the issue description, the full set being attached
+    #[clap(long, default_value_t = DEFAULT_MAX_INLINE_ERRORS)]
+    max_inline_errors: usize,
+    /// Discard the stdout and stderr of fdbserver instead of capturing them, reports then rely on the traces
+    #[clap(long)]
+    no_capture_output: bool,
+    /// Directory where the data and logs directories of faulty seeds are copied to, in `seed_<seed>`
+    #[clap(long, value_name = "DIR")]
+    keep_artifacts: Option<PathBuf>,
+    /// Shell command run with the data directory as argument after each successful simulation,
+    /// the seed is reported as faulty when it fails
+    #[clap(long)]
+    post_seed_validate: Option<String>,
+    /// Only report a seed exiting with a failure status when its traces hold an event matched by
+    /// `--embed-filter`, otherwise record it as suspicious
+    #[clap(long)]
+    faulty_requires_log_event: bool,
+    /// Report a seed exiting successfully as faulty when its traces hold an event of at least this
+    /// severity, e.g. 40 for errors caught by the workload
+    #[clap(long)]
+    fail_on_severity: Option<u32>,
+    /// jq expression selecting the trace events attached to the issue as a file
+    #[clap(long)]
+    attach_filter: Option<String>,
+    /// Regex whose matches are replaced by `***` in reported outputs and logs (repeatable)
+    #[clap(long = "redact-pattern")]
+    redact_patterns: Vec<String>,
+    /// Write a `reproduce_seed_<seed>.sh` script for each faulty seed, into its `--output-dir`
+    /// directory when set, and attach it to the issue
+    #[clap(long)]
+    emit_reproduce_script: bool,
+    /// Environment variable exported by the reproduce scripts besides the `FDB_*` ones (repeatable)
+    #[clap(long = "reproduce-env")]
+    reproduce_env: Vec<String>,
+    /// statsd endpoint (host:port) receiving seed counters and durations
+    #[clap(long, env = "STATSD_ADDR")]
+    statsd_addr: Option<String>,
+    /// Prefix of the metrics sent to statsd
+    #[clap(long, default_value = DEFAULT_STATSD_PREFIX)]
+    statsd_prefix: String,
+    /// Show a progress bar with rate and ETA on stderr, the default when stderr is a terminal
+    #[clap(long, overrides_with = "no_progress")]
+    progress: bool,
+    /// Log a line per checked seed instead of the progress bar
+    #[clap(long, overrides_with = "progress")]
+    no_progress: bool,
+    /// Address (host:port) of an HTTP endpoint serving Prometheus metrics during the run
+    #[clap(long)]
+    metrics_addr: Option<String>,
+    /// Re-run each faulty seed once with `--rerun-verbose-arg` and attach the logs of that run
+    #[clap(long)]
+    rerun_faulty_verbose: bool,
+    /// Extra fdbserver argument of the verbose re-run of faulty seeds (repeatable)
+    #[clap(
+        long = "rerun-verbose-arg",
+        allow_hyphen_values = true,
+        default_values = DEFAULT_RERUN_VERBOSE_ARGS
+    )]
+    rerun_verbose_args: Vec<String>,
+    /// Send issue reports from a pool of this many concurrent tasks instead of the simulation workers
+    #[cfg(feature = "async-reporting")]
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    report_concurrency: Option<u64>,
+    /// File where the run summary is written as JSON, e.g. to be merged with `merge-summaries`
+    #[clap(long, alias = "report-json")]
+    summary_json: Option<PathBuf>,
+    /// File where the outcome of every seed is written as a JUnit XML report for CI
+    #[clap(long)]
+    junit_xml: Option<PathBuf>,
+    /// Directory where the output, filtered events and result of every seed are saved in `seed_<seed>/`
+    #[clap(long)]
+    output_dir: Option<PathBuf>,
+    /// Directory where a `<seed>.json` result file is written for every checked seed
+    #[clap(long)]
+    per_seed_result_dir: Option<PathBuf>,
+    /// Only check a deterministic sample of about one seed in N, selected by hashing the seed
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..))]
+    sample_every: Option<u32>,
+    /// Archive the simfdb data directory of faulty seeds and attach it to the report
+    #[clap(long)]
+    attach_data_dir: bool,
+    /// Labels set on created issues, comma separated; `$VAR` and `${VAR}` are expanded from the environment
+    #[clap(long = "gitlab-labels", value_delimiter = ',')]
+    gitlab_labels: Vec<String>,
+    /// Ids of the users created issues are assigned to, comma separated
+    #[clap(long = "gitlab-assignee-id", value_delimiter = ',')]
+    gitlab_assignee_ids: Vec<u64>,
+    /// Id of the project milestone created issues are filed under
+    #[clap(long)]
+    gitlab_milestone_id: Option<u64>,
+    /// Size in bytes above which an artifact archive is not uploaded to GitLab
+    #[clap(long)]
+    max_upload_bytes: Option<u64>,
+    /// What to do with a label referencing an unset environment variable
+    #[clap(long, value_enum, default_value_t = UnsetLabelVar::Skip)]
+    unset_label_var: UnsetLabelVar,
+    /// Where to post a summary of the run once it is finished
+    #[clap(long, value_enum)]
+    summary_target: Option<SummaryTarget>,
+    /// Merge request receiving the summary note with `--summary-target mr-note`
+    #[clap(long, env = "CI_MERGE_REQUEST_IID")]
+    summary_mr_iid: Option<u64>,
+    /// Slack incoming webhook receiving the summary with `--summary-target slack`
+    #[clap(long, env = "SLACK_WEBHOOK_URL")]
+    slack_webhook_url: Option<String>,
+    /// Largest seed accepted by the fdbserver binary, e.g. 2147483647 for builds expecting a signed seed
+    #[clap(long, default_value_t = MAX_SEED)]
+    seed_max: u32,
+    /// Print every effective setting with the source it comes from (default, env, config or flag),
+    /// then exit
+    #[clap(long)]
+    explain_config: bool,
+    /// Config file setting options by their long flag name, `seeker.toml` by default if present
+    /// Options given as flags or in the environment override it
+    #[clap(long)]
+    config: Option<PathBuf>,
+    /// Cycle through the seeds of `--seeds` and `--seed-file` until another limit stops the run
+    #[clap(long = "loop")]
+    loop_seeds: bool,
+    /// Log a heartbeat with the in-flight seed count every this many seconds without any seed completing
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    heartbeat_secs: Option<u64>,
+    /// File kept up to date with the seeds currently running, one per line
+    #[clap(long)]
+    inflight_file: Option<PathBuf>,
+    /// File recording the completed seeds, whose seeds are skipped to resume an interrupted sweep
+    #[clap(long, conflicts_with = "loop_seeds")]
+    checkpoint: Option<PathBuf>,
+    /// Warn with the in-flight seeds every this many seconds without any seed completing
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    watchdog_secs: Option<u64>,
+    /// Abort the run when no seed completed for this many seconds, a worker is then considered stuck
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    watchdog_abort_secs: Option<u64>,
+    /// Stop dispatching seeds after this many seconds, the in-flight ones still complete
+    #[clap(long)]
+    max_duration_secs: Option<u64>,
+    /// Compress log and data directory archives on every available core
+    #[clap(long)]
+    parallel_compression: bool,
+    /// gzip level of the uploaded artifacts, from 0 (none) to 9 (best), 6 if unset
+    #[clap(long, value_parser = clap::value_parser!(u32).range(0..=9))]
+    artifact_compression: Option<u32>,
+    /// Estimated scratch space (in MB) used by one simulation and its archives
+    #[clap(long, default_value_t = DEFAULT_DISK_SPACE_PER_SEED_MB)]
+    disk_space_per_seed_mb: u64,
+    /// What to do when the scratch volume cannot hold `--chunk-size` simulations
+    #[clap(long, value_enum, default_value_t = DiskSpaceCheck::Warn)]
+    disk_space_check: DiskSpaceCheck,
+    /// Trace event field shown in a table of the reported events (repeatable)
+    #[clap(long = "extract-field")]
+    extract_fields: Vec<String>,
+    /// Snapshot the process state and last trace events of timed-out simulations before terminating them
+    #[clap(long)]
+    timeout_diagnostics: bool,
+    /// Order of the faulty and timed-out seeds listed in the summary
+    #[clap(long, value_enum, default_value_t = SeedOrder::Asc)]
+    sort_seeds: SeedOrder,
+    /// Name of the multipart field holding the file in upload requests
+    #[clap(long, default_value = DEFAULT_UPLOAD_FIELD_NAME)]
+    upload_field_name: String,
+    /// Extra `KEY=VALUE` field added to the multipart form of upload requests (repeatable)
+    #[clap(long = "upload-form-field", value_parser = parse_key_value)]
+    upload_form_fields: Vec<(String, String)>,
+    /// Prefix of the names of the artifacts uploaded to GitLab
+    #[clap(long, default_value = DEFAULT_ARTIFACT_PREFIX)]
+    artifact_prefix: String,
+    /// Run this previously-faulty seed until it passes `--verify-passes` times in a row, failing on the first failure
+    #[clap(long, value_name = "SEED", conflicts_with_all = ["seeds", "seed_file", "seed_range", "loop_seeds"])]
+    verify_fix: Option<u32>,
+    /// Number of consecutive passes required by `--verify-fix`
+    #[clap(long, requires = "verify_fix", default_value_t = DEFAULT_VERIFY_PASSES, value_parser = clap::value_parser!(u32).range(1..))]
+    verify_passes: u32,
+}
+
+#[derive(clap::Subcommand, Debug, Clone)]
+enum Command {
+    /// Report how much of the seed space has been explored from seed records
+    Coverage {
+        /// Seed record files, as written by `--record-seeds`
+        #[clap(required = true)]
+        files: Vec<String>,
+    },
+    /// Measure the harness overhead by dispatching a no-op command instead of fdbserver
+    Bench {
+        /// Number of seeds to dispatch
+        #[clap(long, default_value_t = 1000, value_parser = clap::value_parser!(u32).range(1..))]
+        seeds: u32,
+        /// Number of seeds to run in parallel
+        #[clap(long, default_value_t = DEFAULT_CHUNK_SIZE)]
+        chunk_size: usize,
+        /// Shell command run for each seed in place of fdbserver
+        #[clap(long, default_value = "true")]
+        runner_command: String,
+        /// Prepare the seed workspace inside the job, as before it overlapped with running seeds
+        #[clap(long)]
+        prepare_inline: bool,
+        /// Directory archived with single-threaded and parallel compression to compare their durations
+        #[clap(long)]
+        archive_dir: Option<PathBuf>,
+    },
+    /// Merge the JSON summaries of several runs, e.g. the shards of a run, into one
+    MergeSummaries {
+        /// Summary files, as written by `--summary-json`
+        #[clap(required = true)]
+        files: Vec<PathBuf>,
+        /// File where the merged summary is written instead of the standard output
+        #[clap(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+impl Cli {
+    /// Test file run by the simulations
+    fn test_file(&self) -> &str {
+        self.test_file
+            .get(self.test_file_index)
+            .expect("test file is required when running seeds")
+    }
+
+    /// Options running the `index`th test file instead
+    fn with_test_file(&self, index: usize) -> Cli {
+        Cli {
+            test_file_index: index,
+            ..self.clone()
+        }
+    }
+
+    /// File name of the test file run
+    fn test_file_basename(&self) -> String {
+        std::path::Path::new(self.test_file())
+            .file_name()
+            .map_or_else(
+                || self.test_file().into(),
+                |name| name.to_string_lossy().into_owned(),
+            )
+    }
+
+    /// File name of the test file run, when several are run to tell their faults apart
+    fn test_file_name(&self) -> Option<String> {
+        (self.test_file.len() > 1).then(|| self.test_file_basename())
+    }
+
+    /// Timeout (in seconds) of the simulations of the test file run
+    fn timeout_secs(&self) -> u64 {
+        self.test_file_timeouts
+            .get(self.test_file_index)
+            .copied()
+            .flatten()
+            .unwrap_or(self.timeout_secs)
+    }
+
+    /// Compression of the archived artifacts, gzip's default level unless set
+    fn compression(&self) -> flate2::Compression {
+        self.artifact_compression
+            .map_or_else(flate2::Compression::default, flate2::Compression::new)
+    }
+
+    /// Number of seeds run in parallel, the number of CPU cores unless set
+    fn chunk_size(&self) -> usize {
+        self.chunk_size.unwrap_or_else(available_cores)
+    }
+
+    /// Output paths that must not be shared with a concurrent run
+    fn shared_output_paths(&self) -> Vec<&str> {
+        self.record_seeds
+            .iter()
+            .map(String::as_str)
+            .chain(self.inflight_file.iter().filter_map(|path| path.to_str()))
+            .chain(self.checkpoint.iter().filter_map(|path| path.to_str()))
+            .chain(
+                [
+                    &self.output_dir,
+                    &self.summary_json,
+                    &self.junit_xml,
+                    &self.per_seed_result_dir,
+                ]
+                .into_iter()
+                .filter_map(|path| path.as_deref()?.to_str()),
+            )
+            .collect()
+    }
 }
 
-pub fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// Number of CPU cores available to the process, [`DEFAULT_CHUNK_SIZE`] if unknown
+fn available_cores() -> usize {
+    std::thread::available_parallelism().map_or(DEFAULT_CHUNK_SIZE, usize::from)
+}
+
+/// Parse a `--chunk-size`, either a number of seeds or `auto` for the number of CPU cores
+fn parse_chunk_size(chunk_size: &str) -> Result<usize, String> {
+    if chunk_size == "auto" {
+        return Ok(available_cores());
+    }
+    chunk_size
+        .parse()
+        .map_err(|e| format!("expected a number of seeds or `auto`, got `{chunk_size}`: {e}"))
+}
+
+/// Run the command line, returning how the checked seeds went
+///
+/// Fails with a [`SeekerError`] telling the cause of the failure apart.
+pub fn run() -> Result<RunStatus, SeekerError> {
     dotenv::dotenv().ok();
 
     tracing_subscriber::fmt::init();
 
-    let cli = Cli::parse();
+    // Usage errors are failures of the tool, leave the exit code of a faulty seed to the seeds
+    let (args, config_ids) =
+        config::with_config_args(&Cli::command(), std::env::args_os().collect())?;
+    let matches = match Cli::command().try_get_matches_from(args) {
+        Ok(matches) => matches,
+        Err(e) if !e.use_stderr() => e.exit(),
+        Err(e) => {
+            e.print()?;
+            return Err(SeekerError::Config("Invalid command line".to_string()));
+        }
+    };
+    let mut cli = Cli::from_arg_matches(&matches)?;
+
+    if cli.explain_config {
+        explain_config(&matches, &config_ids);
+        return Ok(RunStatus::Clean);
+    }
+
+    match &cli.command {
+        Some(Command::Coverage { files }) => {
+            report_coverage(files)?;
+            return Ok(RunStatus::Clean);
+        }
+        Some(Command::MergeSummaries { files, output }) => {
+            merge_summaries(files, output.as_deref())?;
+            return Ok(RunStatus::Clean);
+        }
+        Some(Command::Bench {
+            seeds,
+            chunk_size,
+            runner_command,
+            prepare_inline,
+            archive_dir,
+        }) => {
+            let mut stats = bench::bench(*seeds, *chunk_size, runner_command, *prepare_inline)?;
+            if let Some(archive_dir) = archive_dir {
+                stats.bench_compression(archive_dir)?;
+            }
+            println!("{}", serde_json::to_string_pretty(&stats)?);
+            return Ok(RunStatus::Clean);
+        }
+        None => {}
+    }
+
+    if cli.list_test_files
+        && let Some(test_dir) = &cli.test_dir
+    {
+        list_test_files(test_dir)?;
+        return Ok(RunStatus::Clean);
+    }
+
+    // Fail fast on invalid patterns, filters or labels rather than on the first faulty seed
+    Redactor::new(&cli.redact_patterns)?;
+    for (flag, filter) in [
+        ("--embed-filter", Some(&cli.embed_filter)),
+        ("--attach-filter", cli.attach_filter.as_ref()),
+    ] {
+        if let Some(filter) = filter {
+            jq_rs::compile(&log_filter(filter, &cli)).map_err(|e| {
+                SeekerError::Jq(format!("Invalid {flag} jq expression `{filter}`: {e}"))
+            })?;
+        }
+    }
+    issue_labels(&cli)?;
+    check_backend_options(&cli)?;
+    check_redact_options(&cli)?;
+    check_differential_options(&cli)?;
+    simulation::check_fdbserver_args(&cli.fdbserver_args)?;
+
+    // A mistyped path would otherwise fail every seed deep in the workers
+    for fdbserver_path in std::iter::once(&cli.fdbserver_path).chain(&cli.fdbserver_path_b) {
+        let path = preflight::check_fdbserver(fdbserver_path)?;
+        info!(path = %path.display(), "fdbserver binary found");
+    }
+    for test_file in &cli.test_file {
+        preflight::check_test_file(test_file)?;
+    }
+    cli.test_file_timeouts = cli
+        .test_file
+        .iter()
+        .map(|test_file| -> Result<_, SeekerError> {
+            let timeout_secs = test_files::timeout_directive(std::path::Path::new(test_file))?;
+            if let Some(timeout_secs) = timeout_secs {
+                info!(test_file, timeout_secs, "Timeout set by the test file");
+            }
+            Ok(timeout_secs)
+        })
+        .collect::<Result<_, _>>()?;
+
+    if cli.auto_commit_id && cli.commit_id.is_none() {
+        match preflight::head_commit() {
+            Ok((commit_id, dirty_tree)) => {
+                info!(commit_id, dirty_tree, "Commit ID detected");
+                cli.commit_id = Some(commit_id);
+                cli.dirty_tree = dirty_tree;
+            }
+            Err(e) => warn!("Cannot detect the commit ID, running without it: {e}"),
+        }
+    }
+
+    if cli.dry_run {
+        dry_run(&cli)?;
+        return Ok(RunStatus::Clean);
+    }
+
+    let _lock = lock::acquire(&cli.shared_output_paths(), cli.lock_wait)?;
+
+    // Stop cleanly on Ctrl-C, rather than leaving fdbserver processes and temporary directories behind
+    simulation::install_signal_handlers()?;
+
+    // Simulations run in temporary directories, catch a full scratch volume before the first ENOSPC
+    preflight::check_disk_space(
+        &std::env::temp_dir(),
+        cli.disk_space_per_seed_mb,
+        cli.chunk_size(),
+        cli.disk_space_check,
+    )?;
+
+    if cli.collect_core_dumps {
+        simulation::enable_core_dumps()?;
+    }
+
+    if let Some(seed) = cli.verify_fix {
+        return verify_fix(seed, cli.verify_passes, &cli);
+    }
 
     // Build GitLab API client only if token and project_id are provided
     let api: Option<Gitlab> = match (&cli.token, &cli.gitlab_project_id) {
@@ -73,13 +713,44 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
                 project_id, "Export reports to GitLab"
             );
 
-            Some(
-                gitlab::GitlabBuilder::default()
-                    .token(token.as_str())
-                    .endpoint(cli.gitlab_url.as_str())
-                    .project_id(*project_id)
-                    .build()?,
-            )
+            let mut api = gitlab::GitlabBuilder::default()
+                .token(token.as_str())
+                .endpoint(gitlab::parse_endpoint(&cli.gitlab_url)?)
+                .project_id(*project_id)
+                .footer(cli.issue_footer.clone())
+                .upload_field_name(cli.upload_field_name.as_str())
+                .upload_form_fields(cli.upload_form_fields.clone())
+                .parallel_compression(cli.parallel_compression)
+                .compression(cli.compression())
+                .artifact_prefix(cli.artifact_prefix.as_str())
+                .description_limit(cli.issue_description_limit)
+                // Literal `\n` sequences are turned into line breaks, as in the footer
+                .title_template(
+                    cli.issue_title_template
+                        .as_ref()
+                        .map(|template| template.replace("\\n", "\n")),
+                )
+                .body_template(
+                    cli.issue_body_template
+                        .as_ref()
+                        .map(|template| template.replace("\\n", "\n")),
+                )
+                .dedup_issues(cli.dedup_issues)
+                .assignee_ids(cli.gitlab_assignee_ids.clone())
+                .milestone_id(cli.gitlab_milestone_id)
+                .max_upload_bytes(cli.max_upload_bytes)
+                .max_retries(cli.gitlab_max_retries)
+                .retry_base_delay(Duration::from_millis(cli.gitlab_retry_base_delay_ms))
+                .retry_max_backoff(Duration::from_secs(cli.gitlab_retry_max_backoff_secs))
+                .client(gitlab::build_client(
+                    cli.gitlab_ca_cert.as_deref(),
+                    cli.gitlab_insecure,
+                )?)
+                .build()?;
+            if let Some(issue_template) = &cli.gitlab_issue_template {
+                api.load_issue_template(issue_template)?;
+            }
+            Some(api)
         }
         _ => {
             info!("No GitLab API configured, skipping GitLab export");
@@ -87,20 +758,404 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    let user_defined_seeds = merge_user_defined_seeds(cli.seeds.clone(), &cli.seed_file)?;
+    // The summary issue and merge request note stay on GitLab whatever the backend
+    let reporter: Option<std::sync::Arc<dyn IssueReporter>> = match cli.backend {
+        Backend::Gitlab => api
+            .clone()
+            .map(|api| std::sync::Arc::new(api) as std::sync::Arc<dyn IssueReporter>),
+        Backend::Github => {
+            let (Some(token), Some(repo)) = (&cli.github_token, &cli.github_repo) else {
+                return Err(SeekerError::Config(
+                    "--backend github requires a GitHub token and repository".to_string(),
+                ));
+            };
+            info!(repo, "Export reports to GitHub");
+            let github = github::GithubBuilder::default()
+                .token(token.as_str())
+                .repo(repo.as_str())
+                .api_url(cli.github_api_url.as_str())
+                .upload_url(cli.github_upload_url.as_str())
+                .release_id(cli.github_release_id)
+                .footer(cli.issue_footer.clone())
+                .artifact_prefix(cli.artifact_prefix.as_str())
+                .parallel_compression(cli.parallel_compression)
+                .compression(cli.compression())
+                .build()?;
+            Some(std::sync::Arc::new(github))
+        }
+    };
 
-    let seed_iterator = SeedIterator::new(user_defined_seeds);
+    #[cfg(feature = "async-reporting")]
+    if let Some(report_concurrency) = cli.report_concurrency
+        && reporter.is_some()
+    {
+        report_queue::init(report_concurrency as usize)?;
+    }
 
-    if let Some(max_iteration) = cli.max_iterations {
-        run_seeds(
-            seed_iterator.take(max_iteration as usize),
-            &cli,
-            api.as_ref(),
-            cli.chunk_size,
-        )?;
-    } else {
-        run_seeds(seed_iterator, &cli, api.as_ref(), cli.chunk_size)?;
+    // Check the summary can be posted before spending a whole run
+    match cli.summary_target {
+        Some(SummaryTarget::Issue) if api.is_none() => {
+            return Err(SeekerError::Config(
+                "--summary-target issue requires a GitLab token and project id".to_string(),
+            ));
+        }
+        Some(SummaryTarget::MrNote) if api.is_none() || cli.summary_mr_iid.is_none() => {
+            return Err(SeekerError::Config(
+                "--summary-target mr-note requires a GitLab token, project id and --summary-mr-iid"
+                    .to_string(),
+            ));
+        }
+        Some(SummaryTarget::Slack) if cli.slack_webhook_url.is_none() => {
+            return Err(SeekerError::Config(
+                "--summary-target slack requires --slack-webhook-url".to_string(),
+            ));
+        }
+        _ => {}
+    }
+
+    let checkpoint = cli
+        .checkpoint
+        .as_deref()
+        .map(Checkpoint::load)
+        .transpose()?;
+    let seed_iterator = seed_iterator(&cli, checkpoint.as_ref())?;
+
+    let started = Instant::now();
+    let mut summary = run_seeds(seed_iterator, &cli, reporter.clone(), checkpoint)?;
+
+    #[cfg(feature = "async-reporting")]
+    if let Some(report_queue) = report_queue::get() {
+        info!("Waiting for the queued reports to be sent");
+        for issue in report_queue.finish() {
+            summary.add_issue(&issue);
+        }
+    }
+
+    summary.test_category = cli.test_category.clone();
+    summary.commit_id = cli.commit_id.clone();
+    summary.test_file = (!cli.test_file.is_empty()).then(|| cli.test_file.join(", "));
+    summary.duration_secs = started.elapsed().as_secs_f64();
+    summary.sort(cli.sort_seeds);
+    summary.log();
+
+    if let Some(summary_json) = &cli.summary_json {
+        std::fs::write(summary_json, serde_json::to_string_pretty(&summary)?)?;
+    }
+
+    if let Some(summary_target) = cli.summary_target {
+        post_summary(&summary, summary_target, &cli, api.as_ref())?;
+    }
+
+    if simulation::is_interrupted() {
+        return Err(SeekerError::Interrupted(
+            "Run interrupted by a signal".to_string(),
+        ));
+    }
+
+    Ok(summary.status())
+}
+
+/// Seeds to check, as selected by the command line, leaving out the seeds completed in `checkpoint`
+fn seed_iterator(
+    cli: &Cli,
+    checkpoint: Option<&Checkpoint>,
+) -> Result<Box<dyn Iterator<Item = u32>>, SeekerError> {
+    let user_defined_seeds =
+        merge_user_defined_seeds(cli.seeds.clone(), &cli.seed_file, cli.dedup_seeds)?;
+    if let Some(seeds) = &user_defined_seeds {
+        check_seed_range(seeds, cli.seed_max)?;
+    }
+
+    if cli.loop_seeds && user_defined_seeds.is_none() {
+        return Err(SeekerError::Config(
+            "--loop requires seeds from --seeds or --seed-file".to_string(),
+        ));
+    }
+
+    let sample_every = cli.sample_every.unwrap_or(1);
+    // Sample the provided seeds upfront so that looping never spins over filtered out seeds
+    let user_defined_seeds = user_defined_seeds.map(|seeds| {
+        seeds
+            .into_iter()
+            .filter(|seed| is_sampled(*seed, sample_every))
+            .collect::<Vec<_>>()
+    });
+    let mut seed_iterator = match &cli.seed_range {
+        Some(seed_range) => {
+            check_seed_range(&[seed_range.end - 1], cli.seed_max)?;
+            SeedIterator::from_range(seed_range.clone())
+        }
+        None => match cli.random_count {
+            Some(random_count) => SeedIterator::random(random_count).with_max_seed(cli.seed_max),
+            None => SeedIterator::new(user_defined_seeds).with_max_seed(cli.seed_max),
+        },
+    };
+    if let Some(rng_seed) = cli.rng_seed {
+        seed_iterator = seed_iterator.with_rng_seed(rng_seed);
+    }
+    if cli.unique_random {
+        seed_iterator = seed_iterator.unique();
+    }
+    if cli.loop_seeds {
+        seed_iterator = seed_iterator.looping();
+    }
+    let deadline = cli
+        .max_duration_secs
+        .map(|max_duration_secs| Instant::now() + Duration::from_secs(max_duration_secs));
+    let completed = checkpoint
+        .map(|checkpoint| checkpoint.completed().clone())
+        .unwrap_or_default();
+    let seed_iterator = seed_iterator
+        .filter(move |seed| is_sampled(*seed, sample_every))
+        .filter(move |seed| !completed.contains(seed))
+        .take_while(move |_| deadline.is_none_or(|deadline| Instant::now() < deadline));
+
+    Ok(match cli.max_iterations {
+        Some(max_iterations) => Box::new(seed_iterator.take(max_iterations as usize)),
+        None => Box::new(seed_iterator),
+    })
+}
+
+/// Reject the options `--backend github` does not support, rather than silently ignoring them
+fn check_backend_options(cli: &Cli) -> Result<(), SeekerError> {
+    if cli.backend != Backend::Github {
+        return Ok(());
+    }
+    let unsupported = [
+        ("--issue-title-template", cli.issue_title_template.is_some()),
+        ("--issue-body-template", cli.issue_body_template.is_some()),
+        ("--dedup-issues", cli.dedup_issues),
+    ];
+    match unsupported.iter().find(|(_, set)| *set) {
+        Some((flag, _)) => Err(SeekerError::Config(format!(
+            "{flag} is not supported with --backend github"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Reject the options differential mode (`--fdbserver-path-b`) does not apply, rather than
+/// silently ignoring them: only the outcomes of the two binaries are compared
+fn check_differential_options(cli: &Cli) -> Result<(), SeekerError> {
+    if cli.fdbserver_path_b.is_none() {
+        return Ok(());
+    }
+    let unsupported = [
+        ("--faulty-requires-log-event", cli.faulty_requires_log_event),
+        ("--confirm-retries", cli.confirm_retries > 0),
+        ("--fail-on-severity", cli.fail_on_severity.is_some()),
+        ("--post-seed-validate", cli.post_seed_validate.is_some()),
+    ];
+    match unsupported.iter().find(|(_, set)| *set) {
+        Some((flag, _)) => Err(SeekerError::Config(format!(
+            "{flag} is not supported with --fdbserver-path-b"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Reject the artifacts `--redact-pattern` cannot redact, rather than uploading them unredacted
+fn check_redact_options(cli: &Cli) -> Result<(), SeekerError> {
+    if cli.attach_data_dir && !cli.redact_patterns.is_empty() {
+        return Err(SeekerError::Config(
+            "--attach-data-dir is not supported with --redact-pattern, the simfdb data files are binary and cannot be redacted"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Log the fdbserver command line of every seed the run would check, without running anything
+///
+/// Also tells where faulty seeds would be reported, the test file and the fdbserver binaries
+/// having been checked beforehand. No process is spawned, no temporary directory created and
+/// GitLab is not contacted.
+fn dry_run(cli: &Cli) -> Result<(), SeekerError> {
+    match cli.backend {
+        Backend::Gitlab if cli.token.is_some() && cli.gitlab_project_id.is_some() => {
+            info!(
+                host = cli.gitlab_url,
+                project_id = cli.gitlab_project_id,
+                "Faulty seeds would be reported to GitLab"
+            );
+        }
+        Backend::Github if cli.github_token.is_some() && cli.github_repo.is_some() => {
+            info!(
+                repo = cli.github_repo.as_deref(),
+                "Faulty seeds would be reported to GitHub"
+            );
+        }
+        backend => warn!(
+            ?backend,
+            "Reporting not configured, the run would stop at the first faulty seed"
+        ),
+    }
+
+    let checkpoint = cli
+        .checkpoint
+        .as_deref()
+        .map(Checkpoint::load)
+        .transpose()?;
+    let seed_iterator = seed_iterator(cli, checkpoint.as_ref())?;
+    if seed_iterator.size_hint().1.is_none() {
+        return Err(SeekerError::Config(
+            "--dry-run needs a bounded set of seeds, set --max-iterations".to_string(),
+        ));
+    }
+    // Workspaces are temporary directories created for every seed
+    let workspace = std::path::Path::new("<workspace>");
+    let mut count = 0usize;
+    for seed in seed_iterator {
+        for index in 0..cli.test_file.len() {
+            let cli = cli.with_test_file(index);
+            for fdbserver_path in std::iter::once(&cli.fdbserver_path).chain(&cli.fdbserver_path_b)
+            {
+                let (command, test_file) = simulation::command_paths(fdbserver_path, &cli)?;
+                let argv =
+                    simulation::fdbserver_argv(&command, &test_file, workspace, seed, &cli, &[]);
+                info!(seed, argv = argv.join(" "), "Would run");
+            }
+        }
+        count += 1;
+    }
+    info!(seeds = count, "Dry run done");
+
+    Ok(())
+}
+
+/// Post the rendered run summary to `target`, whose settings were checked at startup
+fn post_summary(
+    summary: &Summary,
+    target: SummaryTarget,
+    cli: &Cli,
+    api: Option<&Gitlab>,
+) -> Result<(), SeekerError> {
+    let markdown = summary.render_markdown();
+
+    match (target, api, cli.summary_mr_iid, &cli.slack_webhook_url) {
+        (SummaryTarget::Issue, Some(api), _, _) => {
+            let title = match &cli.commit_id {
+                Some(commit_id) => format!("Seed Seeker run summary for {commit_id}"),
+                None => "Seed Seeker run summary".to_string(),
+            };
+            let issue_url = api.create_summary_issue(&title, &markdown)?;
+            info!(issue_url, "Run summary posted as an issue");
+        }
+        (SummaryTarget::MrNote, Some(api), Some(merge_request_iid), _) => {
+            api.create_merge_request_note(merge_request_iid, &markdown)?;
+            info!(merge_request_iid, "Run summary posted on the merge request");
+        }
+        (SummaryTarget::Slack, _, _, Some(webhook_url)) => {
+            let body = serde_json::json!({ "text": markdown });
+            reqwest::blocking::Client::new()
+                .post(webhook_url)
+                .header("Content-Type", "application/json")
+                .body(body.to_string())
+                .send()?
+                .error_for_status()?;
+            info!("Run summary posted to Slack");
+        }
+        _ => unreachable!("summary target settings are checked at startup"),
+    }
+
+    Ok(())
+}
+
+/// Print the value of every option along with where it comes from
+///
+/// Values of options whose name looks like a secret are masked.
+fn explain_config(matches: &clap::ArgMatches, config_ids: &HashSet<String>) {
+    for arg in Cli::command().get_arguments() {
+        let id = arg.get_id().as_str();
+        let name = match arg.get_long() {
+            Some(long) => format!("--{long}"),
+            None => id.to_string(),
+        };
+        let Some(source) = matches.value_source(id) else {
+            println!("{name}: unset");
+            continue;
+        };
+        let source = match source {
+            clap::parser::ValueSource::DefaultValue => "default".to_string(),
+            clap::parser::ValueSource::EnvVariable => match arg.get_env() {
+                Some(env) => format!("env {}", env.to_string_lossy()),
+                None => "env".to_string(),
+            },
+            clap::parser::ValueSource::CommandLine if config_ids.contains(id) => {
+                "config".to_string()
+            }
+            clap::parser::ValueSource::CommandLine => "flag".to_string(),
+            _ => "unknown".to_string(),
+        };
+        let sensitive = simulation::SENSITIVE_ENV_MARKERS
+            .iter()
+            .any(|marker| id.to_uppercase().contains(marker));
+        let value = if sensitive {
+            "***".to_string()
+        } else {
+            matches
+                .get_raw(id)
+                .map(|values| {
+                    values
+                        .map(|value| value.to_string_lossy().into_owned())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default()
+        };
+        println!("{name}: {value} ({source})");
+    }
+}
+
+fn report_coverage(files: &[String]) -> Result<(), SeekerError> {
+    let coverage = Coverage::from_files(files)?;
+
+    println!("Distinct seeds tested: {}", coverage.distinct_seeds);
+    println!("Seed space size: {}", coverage.space_size);
+    println!("Approximate coverage: {:.6}%", coverage.ratio() * 100.0);
+
+    Ok(())
+}
+
+fn merge_summaries(files: &[PathBuf], output: Option<&std::path::Path>) -> Result<(), SeekerError> {
+    let mut merged: Option<Summary> = None;
+    for file in files {
+        let content = std::fs::read_to_string(file).map_err(|e| {
+            SeekerError::Io(std::io::Error::new(
+                e.kind(),
+                format!("Failed to read summary {}: {e}", file.display()),
+            ))
+        })?;
+        let other: Summary = serde_json::from_str(&content).map_err(|e| {
+            SeekerError::Other(format!("Invalid summary {}: {e}", file.display()).into())
+        })?;
+        match &mut merged {
+            Some(summary) => summary.merge(other),
+            None => merged = Some(other),
+        }
+    }
+    let mut summary = merged.unwrap_or_default();
+    summary.sort(SeedOrder::Asc);
+
+    let json = serde_json::to_string_pretty(&summary)?;
+    match output {
+        Some(output) => std::fs::write(output, json)?,
+        None => println!("{json}"),
+    }
+
+    Ok(())
+}
+
+fn list_test_files(test_dir: &std::path::Path) -> Result<(), SeekerError> {
+    let test_files = test_files::discover(test_dir)?;
+
+    for test_file in &test_files {
+        match &test_file.category {
+            Some(category) => println!("{} ({category})", test_file.path.display()),
+            None => println!("{}", test_file.path.display()),
+        }
     }
+    info!(count = test_files.len(), "Test files discovered");
 
     Ok(())
 }
@@ -108,214 +1163,1056 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
 fn run_seeds(
     seed_iterator: impl Iterator<Item = u32>,
     cli: &Cli,
-    api: Option<&Gitlab>,
-    chunk_size: Option<usize>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    // Use a small worker pool pattern by throttling the number of in-flight tasks to chunk_size.
-    use std::sync::mpsc;
+    reporter: Option<std::sync::Arc<dyn IssueReporter>>,
+    mut checkpoint: Option<Checkpoint>,
+) -> Result<Summary, SeekerError> {
+    let chunk_size = cli.chunk_size();
+    info!(chunk_size, "Running seeds in parallel");
 
-    let chunk_size = chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+    let bar = cli.progress || (!cli.no_progress && std::io::stderr().is_terminal());
+    // Every seed is checked once per test file
+    let mut progress = Progress::new(
+        seed_iterator
+            .size_hint()
+            .1
+            .map(|seeds| seeds * cli.test_file.len()),
+        bar,
+    );
 
-    let size = seed_iterator.size_hint();
+    let mut record = cli
+        .record_seeds
+        .as_ref()
+        .map(|path| File::options().create(true).append(true).open(path))
+        .transpose()?;
 
-    let end = if let Some(end) = size.1 {
-        format!("{end}")
-    } else {
-        "inf".to_string()
-    };
+    let mut summary = Summary::default();
+    // Outcomes are told apart by test file name only when several test files are run
+    let test_file_names: Vec<Option<String>> = (0..cli.test_file.len())
+        .map(|index| cli.with_test_file(index).test_file_name())
+        .collect();
+    let mut junit = cli.junit_xml.as_ref().map(|_| JunitReport::default());
 
-    let (tx, rx) = mpsc::channel::<()>();
-    let mut inflight = 0usize;
-    let mut checked_seeds = 0usize;
+    let mut sinks: Vec<Box<dyn OutcomeSink>> = Vec::new();
+    if let Some(statsd_addr) = &cli.statsd_addr {
+        sinks.push(Box::new(StatsdSink::new(statsd_addr, &cli.statsd_prefix)?));
+    }
+    if let Some(result_dir) = &cli.per_seed_result_dir {
+        sinks.push(Box::new(ResultDirSink::new(result_dir)?));
+    }
+    let mut metrics = None;
+    if let Some(metrics_addr) = &cli.metrics_addr {
+        let sink = PrometheusSink::new(metrics_addr)?;
+        metrics = Some(sink.metrics());
+        sinks.push(Box::new(sink));
+    }
+
+    // Without a reporter the faulty seed is only printed, the run stops there as with --fail-fast
+    let stop_on_faulty = cli.fail_fast || reporter.is_none();
 
     // Shared references for threads
     let cli_arc = std::sync::Arc::new(cli.clone());
-    let api_arc: Option<std::sync::Arc<Gitlab>> = api.cloned().map(std::sync::Arc::new);
+
+    let dispatched = dispatch(
+        seed_iterator,
+        chunk_size,
+        &Monitoring {
+            heartbeat: cli.heartbeat_secs.map(Duration::from_secs),
+            watchdog: cli.watchdog_secs.map(Duration::from_secs),
+            watchdog_abort: cli.watchdog_abort_secs.map(Duration::from_secs),
+            inflight_file: cli.inflight_file.clone(),
+        },
+        || Ok(Workspace::prepare()?),
+        move |seed, workspace| {
+            info!(seed, "Preparing to check seed");
+            if let Some(metrics) = &metrics {
+                metrics.start_simulation();
+            }
+            let outcomes = run_seed_test_files(seed, workspace, &cli_arc, reporter.clone());
+            if let Some(metrics) = &metrics {
+                metrics.end_simulation();
+            }
+            outcomes
+        },
+        |seed, outcomes: Vec<SeedOutcome>, duration| {
+            // Each (seed, test file) pair is accounted for on its own
+            for ((outcome, test_file), test_file_name) in
+                outcomes.iter().zip(&cli.test_file).zip(&test_file_names)
+            {
+                summary.record(seed, test_file_name.as_deref(), outcome);
+                for sink in sinks.iter_mut() {
+                    sink.record(seed, test_file_name.as_deref(), outcome, duration);
+                }
+                if let Some(junit) = &mut junit {
+                    junit.record(seed, test_file, outcome, duration);
+                }
+            }
+            record_seed(&mut record, seed)?;
+            // Seeds which could not be run, or were killed as the run stopped, are run again on resume
+            if let Some(checkpoint) = &mut checkpoint
+                && !outcomes.contains(&SeedOutcome::Error)
+            {
+                checkpoint.record(seed);
+            }
+            progress.update(summary.checked_seeds);
+            if stop_on_faulty
+                && outcomes
+                    .iter()
+                    .any(|outcome| matches!(outcome, SeedOutcome::Faulty { .. }))
+            {
+                warn!(
+                    seed,
+                    "Faulty seed found; stopping the run and killing the in-flight simulations"
+                );
+                simulation::cancel_all();
+                return Ok(ControlFlow::Break(()));
+            }
+            // A burst of faulty seeds almost certainly means a broken build, stop wasting compute
+            if let Some(max_faulty) = cli.max_faulty_before_abort
+                && !summary.aborted
+                && summary.faulty_seeds.len() as u64 >= max_faulty
+            {
+                warn!(
+                    faulty_seeds = summary.faulty_seeds.len(),
+                    "Too many faulty seeds; aborting the run after the in-flight seeds"
+                );
+                summary.aborted = true;
+                return Ok(ControlFlow::Break(()));
+            }
+            Ok(ControlFlow::Continue(()))
+        },
+    );
+
+    progress.finish();
+    if let Some(checkpoint) = &mut checkpoint {
+        checkpoint.save();
+    }
+    if let (Some(junit), Some(junit_xml)) = (&junit, &cli.junit_xml) {
+        junit.write(junit_xml).map_err(|e| {
+            SeekerError::Io(std::io::Error::new(
+                e.kind(),
+                format!(
+                    "Failed to write the JUnit report {}: {e}",
+                    junit_xml.display()
+                ),
+            ))
+        })?;
+    }
+    dispatched?;
+
+    Ok(summary)
+}
+
+/// Run `job` on every seed on a pool of `chunk_size` worker threads, with at most `chunk_size`
+/// seeds in flight
+///
+/// The resources of the next seed are built by `prepare` before waiting for a free slot, so their
+/// setup overlaps with the seeds still running. `complete` is called from the current thread with
+/// the outcome of each seed as it finishes, along with the time the job took. Once it breaks, no
+/// more seeds are dispatched but the in-flight ones still complete.
+///
+/// While waiting for a seed to complete, the dispatcher reports according to `monitoring`, so that
+/// a run stalled on long seeds can be told from a dead one or a stuck worker. No more seeds are
+/// dispatched either once the run is cancelled, e.g. on Ctrl-C.
+fn dispatch<P, O, F, J, C>(
+    seed_iterator: impl Iterator<Item = u32>,
+    chunk_size: usize,
+    monitoring: &Monitoring,
+    mut prepare: F,
+    job: J,
+    mut complete: C,
+) -> Result<(), SeekerError>
+where
+    P: Send + 'static,
+    F: FnMut() -> Result<P, SeekerError>,
+    O: Send + 'static,
+    J: Fn(u32, P) -> O + Send + Sync + 'static,
+    C: FnMut(u32, O, Duration) -> Result<ControlFlow<()>, SeekerError>,
+{
+    use std::sync::{Arc, Mutex, mpsc};
+
+    let chunk_size = chunk_size.max(1);
+    let job = Arc::new(job);
+    let (work_tx, work_rx) = mpsc::channel::<(u32, P)>();
+    let work_rx = Arc::new(Mutex::new(work_rx));
+    let (tx, rx) = mpsc::channel::<(u32, O, Duration)>();
+
+    // Long-lived workers pulling seeds off the shared queue until it is closed
+    let workers: Vec<_> = (0..chunk_size)
+        .map(|worker| {
+            let job = Arc::clone(&job);
+            let work_rx = Arc::clone(&work_rx);
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                loop {
+                    // The lock is released as soon as a seed is received
+                    let received = work_rx.lock().expect("work queue lock poisoned").recv();
+                    let Ok((seed, prepared)) = received else {
+                        break;
+                    };
+                    let _span = tracing::info_span!("seed", seed, worker).entered();
+                    let started = Instant::now();
+                    let outcome = job(seed, prepared);
+                    // Ignore send errors if the receiver is dropped due to early exit
+                    let _ = tx.send((seed, outcome, started.elapsed()));
+                }
+            })
+        })
+        .collect();
+
+    // Seeds in flight with the time they were dispatched at, to point at stuck workers
+    let mut inflight: Vec<(u32, Instant)> = Vec::new();
+    let mut dispatched = 0usize;
+    let mut stopped = false;
+
+    let completed = |inflight: &mut Vec<(u32, Instant)>, checked_seed: u32| {
+        if let Some(index) = inflight.iter().position(|(seed, _)| *seed == checked_seed) {
+            inflight.swap_remove(index);
+        }
+    };
 
     for seed in seed_iterator {
+        let prepared = prepare()?;
+
         // If we already have max parallel jobs running, wait for one to finish.
-        if inflight >= chunk_size {
-            if rx.recv().is_ok() {
-                inflight -= 1;
-                checked_seeds += 1;
-                info!("Progress [{checked_seeds}/{end}]");
-            }
+        if inflight.len() >= chunk_size
+            && let Some((checked_seed, outcome, duration)) =
+                receive(&rx, monitoring, &inflight, dispatched)?
+        {
+            completed(&mut inflight, checked_seed);
+            monitoring.write_inflight(&inflight);
+            stopped = complete(checked_seed, outcome, duration)?.is_break();
         }
 
-        let tx_cloned = tx.clone();
-        let cli_for_thread = std::sync::Arc::clone(&cli_arc);
-        let api_for_thread = api_arc.as_ref().map(std::sync::Arc::clone);
-        info!(seed, "Preparing to check seed");
-        std::thread::spawn(move || {
-            // Note: run_seed may exit the process on faulty seed according to settings.
-            if let Err(e) = run_seed(seed, &cli_for_thread, api_for_thread) {
-                warn!(seed, error = ?e, "failed to run seed");
-            }
-            // Notify completion; ignore send errors if receiver is dropped due to early exit
-            let _ = tx_cloned.send(());
-        });
-        inflight += 1;
+        if stopped || simulation::is_cancelled() {
+            break;
+        }
+
+        dispatched += 1;
+        work_tx
+            .send((seed, prepared))
+            .map_err(|_| SeekerError::Other("every worker thread of the pool exited".into()))?;
+        inflight.push((seed, Instant::now()));
+        monitoring.write_inflight(&inflight);
     }
 
-    // Wait for all in-flight tasks to finish
-    while inflight > 0 {
-        if rx.recv().is_ok() {
-            inflight -= 1;
-            checked_seeds += 1;
-            info!("Progress [{checked_seeds}/{end}]");
+    // Close the queue so that idle workers exit, then wait for all in-flight tasks to finish
+    drop(work_tx);
+    while !inflight.is_empty() {
+        if let Some((checked_seed, outcome, duration)) =
+            receive(&rx, monitoring, &inflight, dispatched)?
+        {
+            completed(&mut inflight, checked_seed);
+            monitoring.write_inflight(&inflight);
+            // Already stopping, the remaining outcomes are only recorded
+            let _ = complete(checked_seed, outcome, duration)?;
+        }
+    }
+    for worker in workers {
+        if worker.join().is_err() {
+            warn!("A worker thread panicked");
         }
     }
 
     Ok(())
 }
 
-fn run_seed(seed: u32, cli: &std::sync::Arc<Cli>, api: Option<std::sync::Arc<Gitlab>>) -> Result<(), Box<dyn std::error::Error>> {
-    info!(seed, "Starting to check seed");
+/// What the dispatcher reports while it waits for a seed to complete
+#[derive(Debug, Default, Clone)]
+struct Monitoring {
+    /// Interval of the heartbeat logs
+    heartbeat: Option<Duration>,
+    /// Interval of the warnings listing the in-flight seeds
+    watchdog: Option<Duration>,
+    /// Wait after which the dispatcher gives up on the in-flight seeds
+    watchdog_abort: Option<Duration>,
+    /// File kept up to date with the in-flight seeds, one per line
+    inflight_file: Option<PathBuf>,
+}
+
+impl Monitoring {
+    /// Rewrite the in-flight file, if any, with the seeds of `inflight`
+    ///
+    /// The file is replaced atomically so that an external watchdog never reads a partial list.
+    fn write_inflight(&self, inflight: &[(u32, Instant)]) {
+        let Some(inflight_file) = &self.inflight_file else {
+            return;
+        };
+        let content: String = inflight
+            .iter()
+            .map(|(seed, _)| format!("{seed}\n"))
+            .collect();
+        let mut tmp_file = inflight_file.as_os_str().to_owned();
+        tmp_file.push(".tmp");
+        if let Err(e) = std::fs::write(&tmp_file, content)
+            .and_then(|_| std::fs::rename(&tmp_file, inflight_file))
+        {
+            warn!(error = ?e, path = %inflight_file.display(), "Failed to write the in-flight seeds file");
+        }
+    }
+}
 
-    let data_dir = tempfile::tempdir()?;
+/// Wait for the next completed seed, reporting according to `monitoring` until one completes
+///
+/// Fails once no seed completed for `monitoring.watchdog_abort`, as a worker is then most likely
+/// stuck for good, e.g. in a network call without timeout.
+fn receive<T>(
+    rx: &std::sync::mpsc::Receiver<T>,
+    monitoring: &Monitoring,
+    inflight: &[(u32, Instant)],
+    dispatched: usize,
+) -> Result<Option<T>, SeekerError> {
+    use std::sync::mpsc::RecvTimeoutError;
 
-    let simfdb_data_dir = data_dir.path().join("simfdb");
-    let logs_dir = data_dir.path().join("logs");
+    let waiting_since = Instant::now();
+    let mut next_heartbeat = monitoring
+        .heartbeat
+        .map(|interval| waiting_since + interval);
+    let mut next_warning = monitoring.watchdog.map(|interval| waiting_since + interval);
+    let abort_at = monitoring.watchdog_abort.map(|limit| waiting_since + limit);
 
-    std::fs::create_dir_all(&logs_dir)?;
+    loop {
+        let Some(wake_up) = [next_heartbeat, next_warning, abort_at]
+            .into_iter()
+            .flatten()
+            .min()
+        else {
+            return Ok(rx.recv().ok());
+        };
+        match rx.recv_timeout(wake_up.saturating_duration_since(Instant::now())) {
+            Ok(completed) => return Ok(Some(completed)),
+            Err(RecvTimeoutError::Disconnected) => return Ok(None),
+            Err(RecvTimeoutError::Timeout) => {}
+        }
 
-    let config = PopenConfig {
-        stdout: Redirection::Pipe,
-        stderr: Redirection::Pipe,
-        ..Default::default()
-    };
+        let now = Instant::now();
+        let waited_secs = now.duration_since(waiting_since).as_secs();
+        let inflight_seeds = || {
+            inflight
+                .iter()
+                .map(|(seed, since)| format!("{seed} ({}s)", since.elapsed().as_secs()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
 
-    let mut process = subprocess::Popen::create(
-        &[
-            cli.fdbserver_path.as_str(),
-            "-r",
-            "simulation",
-            "-b",
-            "on",
-            "--trace-format",
-            "json",
-            "-f",
-            cli.test_file.as_str(),
-            "-d",
-            simfdb_data_dir
-                .to_str()
-                .expect("failed to get simfdb data dir path"),
-            "-L",
-            logs_dir.to_str().expect("failed to get logs dir path"),
-            "-s",
-            &seed.to_string(),
-        ],
-        config,
-    )?;
+        if abort_at.is_some_and(|abort_at| now >= abort_at) {
+            return Err(SeekerError::Timeout(format!(
+                "No seed completed for {waited_secs}s, a worker is stuck; in-flight seeds (running for): {}",
+                inflight_seeds()
+            )));
+        }
+        if let Some(heartbeat) = next_heartbeat
+            && now >= heartbeat
+        {
+            info!(
+                inflight = inflight.len(),
+                dispatched,
+                completed = dispatched - inflight.len(),
+                "Heartbeat: waiting for in-flight seeds"
+            );
+            next_heartbeat = monitoring.heartbeat.map(|interval| now + interval);
+        }
+        if let Some(warning) = next_warning
+            && now >= warning
+        {
+            warn!(
+                waited_secs,
+                inflight = inflight_seeds(),
+                "No seed completed for a while, a worker may be stuck"
+            );
+            next_warning = monitoring.watchdog.map(|interval| now + interval);
+        }
+    }
+}
+
+/// Run `seed` over and over, each time in a fresh workspace, until it passed `passes` times in a row
+///
+/// The seed is already known to be faulty, so failures are not reported to GitLab: the first one
+/// ends the verification, with the status of its outcome.
+fn verify_fix(seed: u32, passes: u32, cli: &Cli) -> Result<RunStatus, SeekerError> {
+    check_seed_range(&[seed], cli.seed_max)?;
+    let cli = std::sync::Arc::new(cli.clone());
+
+    for consecutive_passes in 0..passes {
+        let outcomes = run_seed_test_files(seed, Workspace::prepare()?, &cli, None);
+        if let Some(outcome) = outcomes.iter().find(|outcome| **outcome != SeedOutcome::Ok) {
+            let message = format!(
+                "Fix not verified: seed {seed} was {} after {consecutive_passes} consecutive passes",
+                outcome.name()
+            );
+            return match outcome {
+                SeedOutcome::Error => Err(SeekerError::Other(message.into())),
+                SeedOutcome::TimedOut => {
+                    warn!("{message}");
+                    Ok(RunStatus::TimedOut)
+                }
+                _ => {
+                    warn!("{message}");
+                    Ok(RunStatus::Faulty)
+                }
+            };
+        }
+        info!(
+            seed,
+            consecutive_passes = consecutive_passes + 1,
+            passes,
+            "Seed passed"
+        );
+    }
+
+    info!(seed, passes, "Fix verified, the seed passed every run");
+    Ok(RunStatus::Clean)
+}
+
+/// Append a checked seed to the seed record file if any
+fn record_seed(record: &mut Option<File>, seed: u32) -> Result<(), SeekerError> {
+    if let Some(record) = record {
+        writeln!(record, "{seed}")?;
+    }
+    Ok(())
+}
+
+/// Run `seed` against every test file in turn, each in its own workspace
+///
+/// Returns the outcome of each test file, a test file which could not be run being an error.
+fn run_seed_test_files(
+    seed: u32,
+    workspace: Workspace,
+    cli: &std::sync::Arc<Cli>,
+    reporter: Option<std::sync::Arc<dyn IssueReporter>>,
+) -> Vec<SeedOutcome> {
+    let mut workspace = Some(workspace);
+    (0..cli.test_file.len())
+        .map(|index| {
+            let cli = std::sync::Arc::new(cli.with_test_file(index));
+            let workspace = match workspace.take() {
+                Some(workspace) => Ok(workspace),
+                None => Workspace::prepare(),
+            };
+            workspace
+                .map_err(Into::into)
+                .and_then(|workspace| run_seed(seed, workspace, &cli, reporter.clone()))
+                .unwrap_or_else(|e| {
+                    warn!(seed, test_file = cli.test_file(), error = ?e, "failed to run seed");
+                    SeedOutcome::Error
+                })
+        })
+        .collect()
+}
+
+fn run_seed(
+    seed: u32,
+    workspace: Workspace,
+    cli: &std::sync::Arc<Cli>,
+    reporter: Option<std::sync::Arc<dyn IssueReporter>>,
+) -> Result<SeedOutcome, SeekerError> {
+    info!(seed, "Starting to check seed");
+
+    let started = Instant::now();
+    let simulation = simulate(&cli.fdbserver_path, seed, cli, workspace, &[])?;
+    if let Some(output_dir) = &cli.output_dir {
+        save_seed_output(
+            &seed_output_dir(output_dir, seed, cli),
+            &simulation,
+            started.elapsed(),
+            cli,
+        );
+    }
 
-    match process.wait_timeout(Duration::from_secs(cli.timeout_secs)) {
-        Ok(Some(exit_status)) => {
-            // Process finished within timeout; now read stdout/stderr
-            let (stdout, stderr) = process.communicate(None)?;
-            if !exit_status.success() {
-                handle_faulty_seed(
-                    &logs_dir,
-                    stdout,
-                    stderr,
+    let Some(fdbserver_path_b) = &cli.fdbserver_path_b else {
+        if simulation.is_faulty()
+            && cli.faulty_requires_log_event
+            && !has_faulty_log_event(&simulation, cli)?
+        {
+            warn!(
+                seed,
+                exit_status = ?simulation.exit_status,
+                "Failure exit status without any faulty trace event; recording the seed as suspicious"
+            );
+            return Ok(SeedOutcome::Suspicious);
+        }
+        if simulation.is_faulty() && cli.confirm_retries > 0 {
+            let (reproduced, reruns) = confirm_faulty_seed(seed, cli)?;
+            let confirmed = match cli.confirm_mode {
+                ConfirmMode::Any => reproduced > 0,
+                ConfirmMode::All => reproduced == reruns,
+            };
+            if !confirmed {
+                warn!(
                     seed,
-                    cli.commit_id.clone(),
-                    api.as_deref(),
-                    cli.fail_fast,
-                )?;
-            } else {
-                info!(seed, "Finished check seed no error found");
+                    reproduced,
+                    reruns,
+                    "Failure not confirmed by the re-runs; recording the seed as suspicious"
+                );
+                return Ok(SeedOutcome::Suspicious);
             }
+            let confirmation = format!("reproduced {reproduced}/{reruns}");
+            return handle_faulty_seed(
+                simulation,
+                seed,
+                cli,
+                reporter.as_ref(),
+                None,
+                Some(confirmation),
+            );
+        }
+        if simulation.is_faulty() || (simulation.timed_out() && cli.timeout_is_failure) {
+            return handle_faulty_seed(simulation, seed, cli, reporter.as_ref(), None, None);
+        } else if simulation.timed_out() {
+            return Ok(SeedOutcome::TimedOut);
         }
-        Ok(None) => {
-            // Timed out
+        if let Some(severity) = cli.fail_on_severity {
+            let filtered_logs = filter_logs(
+                &simulation.logs_dir(),
+                &log_filter(&severity_filter(severity), cli),
+            )?;
+            if !filtered_logs.output.is_empty() {
+                warn!(
+                    seed,
+                    severity, "Successful exit with trace events of the failing severity"
+                );
+                return handle_faulty_seed(simulation, seed, cli, reporter.as_ref(), None, None);
+            }
+        }
+        if let Some(validate_command) = &cli.post_seed_validate {
+            let mut simulation = simulation;
+            if !simulation.validate(validate_command)? {
+                warn!(seed, "Post-seed validation of the data directory failed");
+                return handle_faulty_seed(simulation, seed, cli, reporter.as_ref(), None, None);
+            }
+        }
+        info!(seed, "Finished check seed no error found");
+        return Ok(SeedOutcome::Ok);
+    };
+
+    // Differential mode: the same seed runs on the second binary and only divergences are reported
+    let started = Instant::now();
+    let simulation_b = simulate(fdbserver_path_b, seed, cli, Workspace::prepare()?, &[])?;
+    if let Some(output_dir) = &cli.output_dir {
+        save_seed_output(
+            &seed_output_dir(output_dir, seed, cli).join("b"),
+            &simulation_b,
+            started.elapsed(),
+            cli,
+        );
+    }
+
+    match describe_divergence(
+        &cli.fdbserver_path,
+        &simulation,
+        fdbserver_path_b,
+        &simulation_b,
+    ) {
+        Some(divergence) => {
             warn!(
                 seed,
-                timeout_secs = cli.timeout_secs,
-                "Timeout reached; terminating process and continuing"
+                divergence, "Outcomes diverged between fdbserver binaries"
             );
-            if let Err(e) = process.terminate() {
-                warn!(seed, error = ?e, "Failed to terminate process");
-            }
-            // Do not treat as error; continue with next seeds
+            // Report the failing run, preferring the second binary when both failed
+            let diverged = if simulation_b.is_faulty() {
+                simulation_b
+            } else {
+                simulation
+            };
+            handle_faulty_seed(
+                diverged,
+                seed,
+                cli,
+                reporter.as_ref(),
+                Some(divergence),
+                None,
+            )
         }
-        Err(e) => {
-            // An actual error while waiting; try to terminate and bubble up the error
-            warn!(seed, error = ?e, "Error while waiting for process; terminating");
-            if let Err(e2) = process.terminate() {
-                warn!(seed, error = ?e2, "Failed to terminate process");
+        None if simulation.timed_out() || simulation_b.timed_out() => {
+            warn!(
+                seed,
+                "Timeout reached on one of the binaries; outcomes not compared"
+            );
+            Ok(SeedOutcome::TimedOut)
+        }
+        None => {
+            info!(seed, "Finished check seed, both binaries behaved the same");
+            Ok(SeedOutcome::Ok)
+        }
+    }
+}
+
+/// Directory of `output_dir` holding the output of `seed`, one per test file when several are run
+fn seed_output_dir(output_dir: &std::path::Path, seed: u32, cli: &Cli) -> PathBuf {
+    let dir = output_dir.join(format!("seed_{seed}"));
+    match cli.test_file_name() {
+        Some(test_file_name) => dir.join(test_file_name),
+        None => dir,
+    }
+}
+
+/// File name of the reproduce script of `seed`, with the test file when several are run
+fn reproduce_script_name(seed: u32, cli: &Cli) -> String {
+    match cli.test_file_name() {
+        Some(test_file_name) => {
+            let stem = std::path::Path::new(&test_file_name)
+                .file_stem()
+                .map_or(test_file_name.clone(), |stem| {
+                    stem.to_string_lossy().into_owned()
+                });
+            format!("reproduce_seed_{seed}_{stem}.sh")
+        }
+        None => format!("reproduce_seed_{seed}.sh"),
+    }
+}
+
+/// Re-run the faulty `seed` up to `--confirm-retries` times, each time in a fresh workspace
+///
+/// Stops as soon as the outcome is settled for `--confirm-mode`. Returns how many re-runs
+/// reproduced the failure, and how many were run.
+fn confirm_faulty_seed(seed: u32, cli: &Cli) -> Result<(u32, u32), SeekerError> {
+    let mut reproduced = 0;
+    let mut reruns = 0;
+    while reruns < cli.confirm_retries && !simulation::is_cancelled() {
+        info!(
+            seed,
+            rerun = reruns + 1,
+            confirm_retries = cli.confirm_retries,
+            "Re-running faulty seed to confirm the failure"
+        );
+        let simulation = simulate(&cli.fdbserver_path, seed, cli, Workspace::prepare()?, &[])?;
+        reruns += 1;
+        if simulation.is_faulty() {
+            reproduced += 1;
+        }
+        let settled = match cli.confirm_mode {
+            ConfirmMode::Any => reproduced > 0,
+            ConfirmMode::All => reproduced < reruns,
+        };
+        if settled {
+            break;
+        }
+    }
+    Ok((reproduced, reruns))
+}
+
+/// Save the output, filtered events and result of `simulation` in `dir`, whatever its outcome
+///
+/// `result.json` is written last and atomically, so a directory without it holds a partial
+/// output. Failures are logged rather than failing the seed.
+fn save_seed_output(dir: &std::path::Path, simulation: &Simulation, duration: Duration, cli: &Cli) {
+    let save = || -> Result<(), SeekerError> {
+        std::fs::create_dir_all(dir)?;
+        let redactor = Redactor::new(&cli.redact_patterns)?;
+        for (name, output) in [
+            ("stdout.txt", &simulation.stdout),
+            ("stderr.txt", &simulation.stderr),
+        ] {
+            if let Some(output) = output {
+                std::fs::write(dir.join(name), redactor.redact(output))?;
             }
-            return Err(Box::<dyn std::error::Error>::from(e));
+        }
+        let filtered_logs =
+            filter_logs(&simulation.logs_dir(), &log_filter(&cli.embed_filter, cli))?;
+        std::fs::write(
+            dir.join("filtered.json"),
+            redactor.redact(&filtered_logs.output),
+        )?;
+
+        let (exit_code, signal) = match simulation.exit_status {
+            Some(subprocess::ExitStatus::Exited(code)) => (Some(code), None),
+            Some(subprocess::ExitStatus::Signaled(signal)) => (None, Some(signal)),
+            _ => (None, None),
+        };
+        let result = serde_json::json!({
+            "exit_code": exit_code,
+            "signal": signal,
+            "timed_out": simulation.timed_out(),
+            "duration_secs": duration.as_secs_f64(),
+        });
+        let tmp_file = dir.join("result.json.tmp");
+        std::fs::write(&tmp_file, serde_json::to_string_pretty(&result)?)?;
+        std::fs::rename(&tmp_file, dir.join("result.json"))?;
+        Ok(())
+    };
+    if let Err(e) = save() {
+        warn!(error = ?e, dir = %dir.display(), "Failed to save the output of the seed");
+    }
+}
+
+/// Whether the traces of `simulation` hold an event selected by the issue report filter
+fn has_faulty_log_event(simulation: &Simulation, cli: &Cli) -> Result<bool, SeekerError> {
+    let filtered_logs = filter_logs(&simulation.logs_dir(), &log_filter(&cli.embed_filter, cli))?;
+    Ok(!filtered_logs.output.is_empty())
+}
+
+/// Expand the user-defined issue labels, leaving out empty ones
+fn issue_labels(cli: &Cli) -> Result<Vec<String>, SeekerError> {
+    let mut labels = Vec::new();
+    for template in &cli.gitlab_labels {
+        if let Some(label) = expand_label(template, cli.unset_label_var)?
+            && !label.trim().is_empty()
+        {
+            labels.push(label.trim().to_string());
         }
     }
+    Ok(labels)
+}
 
-    Ok(())
+/// Build the jq filter selecting the trace events reported for a faulty seed
+fn log_filter(filter: &str, cli: &Cli) -> String {
+    let mut filter = filter.to_string();
+    if let Some(machine) = &cli.trace_machine {
+        // A JSON string literal is a valid jq string literal
+        let machine = serde_json::Value::from(machine.as_str());
+        filter.push_str(&format!(" | select(.Machine=={machine})"));
+    }
+    filter
 }
 
 fn handle_faulty_seed(
-    logs_dir: &PathBuf,
-    stdout: Option<String>,
-    stderr: Option<String>,
+    mut simulation: Simulation,
     seed: u32,
-    commit_id: Option<String>,
-    api: Option<&Gitlab>,
-    fail_fast: bool,
-) -> Result<(), Box<dyn std::error::Error>> {
-    warn!(seed, "Faulty seed found");
+    cli: &Cli,
+    reporter: Option<&std::sync::Arc<dyn IssueReporter>>,
+    divergence: Option<String>,
+    confirmation: Option<String>,
+) -> Result<SeedOutcome, SeekerError> {
+    let exit_status = simulation.exit_status.map(simulation::describe_exit_status);
+    warn!(
+        seed,
+        exit_status = exit_status.as_deref(),
+        "Faulty seed found"
+    );
 
-    // Build filtered_output from logs (Rust layer, severity 40)
-    let mut compiled = jq_rs::compile(r#"select(.Layer=="Rust") | select(.Severity=="40")"#)?;
+    if let Some(reporter) = reporter
+        && !reporter.claim_report(seed, cli.test_file())
+    {
+        info!(
+            seed,
+            "Seed already reported during this run, not filing another issue"
+        );
+        return Ok(SeedOutcome::Faulty {
+            issue: None,
+            filtered_output: None,
+        });
+    }
 
-    let mut filtered_output = String::new();
+    let logs_dir = simulation.logs_dir();
 
-    for file in walkdir::WalkDir::new(logs_dir.clone()) {
-        let file = file?;
-        if file.path().extension().unwrap_or_default() == "json" {
-            let file = std::fs::File::open(file.path())?;
-            let reader = std::io::BufReader::new(file);
+    // Mask secrets before anything is printed, filtered or uploaded
+    let redactor = Redactor::new(&cli.redact_patterns)?;
+    redactor.redact_dir(&logs_dir)?;
+    simulation.stdout = simulation.stdout.map(|out| redactor.redact(&out));
+    simulation.stderr = simulation.stderr.map(|err| redactor.redact(&err));
+    simulation.validation_failure = simulation
+        .validation_failure
+        .map(|output| redactor.redact(&output));
 
-            for line in reader.lines() {
-                let logs = compiled.run(&line?)?;
-                if logs.is_empty() {
-                    continue;
-                }
-                let pretty = jsonxf::pretty_print(&logs)?;
-                filtered_output.push_str(&pretty);
-                filtered_output.push('\n');
+    if let Some(keep_artifacts) = &cli.keep_artifacts {
+        match simulation.keep_artifacts(keep_artifacts, seed) {
+            Ok(kept) => {
+                info!(seed, path = %kept.display(), "Kept the data and logs of the faulty seed")
+            }
+            Err(e) => {
+                warn!(seed, error = ?e, "Failed to keep the data and logs of the faulty seed")
             }
         }
     }
 
-    // If no GitLab API is configured, display stdout, stderr, and filtered_output then exit faulty
-    if api.is_none() {
-        println!("stdout:\n");
-        if let Some(out) = &stdout {
+    // A re-run with more verbose tracing often gives better diagnostics than the original run
+    let verbose_simulation = if cli.rerun_faulty_verbose {
+        info!(seed, "Re-running faulty seed with verbose tracing");
+        match simulate(
+            &simulation.fdbserver_path,
+            seed,
+            cli,
+            Workspace::prepare()?,
+            &cli.rerun_verbose_args,
+        ) {
+            Ok(verbose_simulation) => {
+                redactor.redact_dir(&verbose_simulation.logs_dir())?;
+                Some(verbose_simulation)
+            }
+            Err(e) => {
+                warn!(seed, error = ?e, "Failed to re-run faulty seed with verbose tracing");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // Build filtered_output from logs (Rust layer, severity 40 by default), streaming the full
+    // set of events to a file next to the logs rather than holding it in memory
+    let filtered_events = logs_dir.with_file_name("filtered_events.json");
+    let filtered_logs = filter_logs_to(
+        &logs_dir,
+        &log_filter(&cli.embed_filter, cli),
+        cli.max_inline_errors,
+        &mut std::io::BufWriter::new(File::create(&filtered_events)?),
+    )?;
+    let filtered_output = filtered_logs.output;
+    let omitted_events = filtered_logs.omitted_events;
+    if omitted_events > 0 {
+        info!(
+            seed,
+            omitted_events,
+            max_inline_errors = cli.max_inline_errors,
+            "Too many trace events to embed in the report, attaching the full set"
+        );
+    }
+    let skipped_log_lines = filtered_logs.skipped_lines;
+    // An empty report would hide a logging misconfiguration behind an uninformative issue
+    let missing_traces = filtered_logs.trace_files == 0;
+    if missing_traces {
+        warn!(
+            seed,
+            logs_dir = %logs_dir.display(),
+            "No JSON trace file was written, fdbserver logging may be misconfigured (-L, --trace-format)"
+        );
+    }
+    let extracted_fields = if cli.extract_fields.is_empty() {
+        None
+    } else {
+        extract_fields(&filtered_output, &cli.extract_fields)
+    };
+    // Consistency check failures are the most serious bugs, detect them whatever the filters
+    let consistency_failures = filter_logs(&logs_dir, CONSISTENCY_CHECK_FILTER)?.output;
+    let consistency_failures = if consistency_failures.is_empty() {
+        None
+    } else {
+        warn!(seed, "Consistency check failure detected");
+        Some(consistency_failures)
+    };
+    let attached_output = cli
+        .attach_filter
+        .as_ref()
+        .map(|attach_filter| filter_logs(&logs_dir, &log_filter(attach_filter, cli)))
+        .transpose()?
+        .map(|attached_logs| attached_logs.output);
+
+    let reproduce_script = if cli.emit_reproduce_script {
+        let script = redactor.redact(&simulation::reproduce_script(
+            &simulation.fdbserver_path,
+            seed,
+            cli,
+        )?);
+        // Kept with the seed output when saved, next to the logs of the workspace otherwise, so
+        // that concurrent runs and test files never overwrite each other's scripts
+        let dir = match &cli.output_dir {
+            Some(output_dir) => {
+                let dir = seed_output_dir(output_dir, seed, cli);
+                std::fs::create_dir_all(&dir)?;
+                dir
+            }
+            None => simulation.workspace_dir().to_path_buf(),
+        };
+        let path = dir.join(reproduce_script_name(seed, cli));
+        std::fs::write(&path, &script)?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        info!(seed, path = %path.display(), "Reproduce script written");
+        Some((script, path))
+    } else {
+        None
+    };
+
+    let data_dir = if cli.attach_data_dir {
+        let data_dir = simulation.data_dir();
+        let size: u64 = walkdir::WalkDir::new(&data_dir)
+            .into_iter()
+            .filter_map(|entry| entry.ok()?.metadata().ok())
+            .filter(|metadata| metadata.is_file())
+            .map(|metadata| metadata.len())
+            .sum();
+        if size > LARGE_DATA_DIR_BYTES {
+            warn!(
+                seed,
+                size_bytes = size,
+                "Data directory is large, archiving and attaching it may take a while"
+            );
+        }
+        Some(data_dir)
+    } else {
+        None
+    };
+
+    // If no reporter is configured, display stdout, stderr, and filtered_output then exit faulty
+    if reporter.is_none() {
+        if let Some(consistency_failures) = &consistency_failures {
+            println!("CONSISTENCY CHECK FAILURE:\n");
+            println!("{}", consistency_failures);
+        }
+        if missing_traces {
+            println!(
+                "NO TRACE FILE WRITTEN: fdbserver logging may be misconfigured (-L, --trace-format)\n"
+            );
+        }
+        if let Some(divergence) = &divergence {
+            println!("divergence:\n");
+            println!("{}", divergence);
+        }
+        if let Some(exit_status) = &exit_status {
+            println!("fdbserver {exit_status}\n");
+        }
+        if let Some(confirmation) = &confirmation {
+            println!("confirmation re-runs: {confirmation}\n");
+        }
+        if simulation.timed_out() {
+            println!("timed out after {}s\n", cli.timeout_secs());
+        }
+        if let Some(timeout_diagnostics) = &simulation.timeout_diagnostics {
+            println!("timeout diagnostics:\n");
+            println!("{}", timeout_diagnostics);
+        }
+        if let Some(validation_failure) = &simulation.validation_failure {
+            println!("post-seed validation failed:\n");
+            println!("{}", validation_failure);
+        }
+        if let Some(test_category) = &cli.test_category {
+            println!("test category: {test_category}\n");
+        }
+        if let Some(simulation_count) = cli.simulation_count {
+            println!("simulation count: {simulation_count}\n");
+        }
+        if let Some(out) = &simulation.stdout {
+            println!("stdout:\n");
             println!("{}", out);
         }
-        println!("stderr:\n");
-        if let Some(err) = &stderr {
+        if let Some(err) = &simulation.stderr {
+            println!("stderr:\n");
             eprintln!("{}", err);
         }
+        if let Some(extracted_fields) = &extracted_fields {
+            println!("extracted fields:\n");
+            println!("{}", extracted_fields);
+        }
         println!("layer errors (filtered_output):\n");
         if !filtered_output.is_empty() {
             println!("{}", filtered_output);
         }
-        std::process::exit(1)
+        if omitted_events > 0 {
+            println!("… {omitted_events} more events truncated, see the full logs\n");
+        }
+        if skipped_log_lines > 0 {
+            println!("skipped malformed log lines: {skipped_log_lines}\n");
+        }
+        if let Some(attached_output) = &attached_output {
+            println!("attached events:\n");
+            println!("{}", attached_output);
+        }
+        if let Some((_, path)) = &reproduce_script {
+            if cli.output_dir.is_some() {
+                println!("reproduce script: {}\n", path.display());
+            } else {
+                println!(
+                    "reproduce script: {} (attached to the issue, set --output-dir to keep it)\n",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                );
+            }
+        }
+        if let Some(verbose_simulation) = &verbose_simulation {
+            println!(
+                "verbose re-run exit status: {:?}\n",
+                verbose_simulation.exit_status
+            );
+            println!("verbose re-run layer errors:\n");
+            println!(
+                "{}",
+                filter_logs(
+                    &verbose_simulation.logs_dir(),
+                    &log_filter(&cli.embed_filter, cli)
+                )?
+                .output
+            );
+        }
+        if let Some(data_dir) = &data_dir {
+            // The data dir is removed on exit, keep an archive in the working directory
+            let saved = PathBuf::from(format!("simfdb_seed_{seed}.tar.gz"));
+            gitlab::archive_dir(
+                data_dir,
+                &saved,
+                cli.parallel_compression,
+                cli.compression(),
+            )?;
+            println!("data directory saved to {}", saved.display());
+        }
+        if let Some(core_dump) = &simulation.core_dump {
+            // The data dir is removed on exit, keep the core dump in the working directory
+            let saved = PathBuf::from(format!("core_seed_{seed}"));
+            std::fs::copy(core_dump, &saved)?;
+            println!("core dump saved to {}", saved.display());
+        }
+        return Ok(SeedOutcome::Faulty {
+            issue: None,
+            filtered_output: Some(filtered_output),
+        });
+    }
+
+    let mut labels = issue_labels(cli)?;
+    labels.extend(cli.test_category.iter().cloned());
+    if cli.label_test_file || cli.test_file.len() > 1 {
+        labels.extend(test_files::label(std::path::Path::new(cli.test_file())));
+    }
+    if consistency_failures.is_some() {
+        labels.push(CONSISTENCY_CHECK_LABEL.to_string());
     }
 
     let payload = PayloadBuilder::default()
         .logs(logs_dir)
-        .filtered_output(filtered_output)
-        .stdout(stdout)
-        .stderr(stderr)
+        .filtered_output(filtered_output.clone())
+        .omitted_events(omitted_events)
+        .filtered_events((omitted_events > 0).then_some(filtered_events))
+        .skipped_log_lines(skipped_log_lines)
+        .missing_traces(missing_traces)
+        .extracted_fields(extracted_fields)
+        .attached_output(attached_output)
+        .stdout(simulation.stdout.clone())
+        .stderr(simulation.stderr.clone())
         .seed(seed)
-        .commit_id(commit_id)
+        .commit_id(cli.commit_id.clone())
+        .dirty_tree(cli.dirty_tree)
+        .divergence(divergence)
+        .core_dump(simulation.core_dump.clone())
+        .reproduce_script(reproduce_script.map(|(script, _)| script))
+        .data_dir(data_dir)
+        .verbose_logs(
+            verbose_simulation
+                .as_ref()
+                .map(|verbose_simulation| verbose_simulation.logs_dir()),
+        )
+        .simulation_count(cli.simulation_count)
+        .test_category(cli.test_category.clone())
+        .test_file(cli.test_file_name())
+        .test_file_basename(Some(cli.test_file_basename()))
+        .exit_status(exit_status)
+        .confirmation(confirmation)
+        .timeout_secs(simulation.timed_out().then_some(cli.timeout_secs()))
+        .timeout_diagnostics(simulation.timeout_diagnostics.clone())
+        .validation_failure(simulation.validation_failure.clone())
+        .labels(labels)
+        .consistency_failures(consistency_failures)
         .build()?;
 
-    if let Some(api) = api {
-        api.create_issue(payload)?;
-        if fail_fast {
-            std::process::exit(1)
-        }
+    #[cfg(feature = "async-reporting")]
+    if let (Some(reporter), Some(report_queue)) = (reporter, report_queue::get()) {
+        // The simulations own the logs uploaded with the report
+        report_queue.submit(
+            seed,
+            std::sync::Arc::clone(reporter),
+            payload,
+            (simulation, verbose_simulation),
+        );
+        info!(seed, "Issue report queued for faulty seed");
+        // Accounted for when the queue is drained at the end of the run
+        return Ok(SeedOutcome::Faulty {
+            issue: None,
+            filtered_output: Some(filtered_output),
+        });
     }
-    Ok(())
+
+    let issue = reporter
+        .map(|reporter| reporter.report(payload))
+        .transpose()?;
+    if let Some(ReportedIssue::Created(issue_url)) = &issue {
+        info!(seed, issue_url, "Issue created for faulty seed");
+    }
+    Ok(SeedOutcome::Faulty {
+        issue,
+        filtered_output: Some(filtered_output),
+    })
 }
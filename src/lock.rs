@@ -0,0 +1,82 @@
+use crate::error::SeekerError;
+use std::fs::{File, TryLockError};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Advisory locks on the shared output paths of a run
+///
+/// Each output path is guarded by a sibling `<path>.lock` file. The locks are released
+/// when this value is dropped, and by the OS whenever the process exits, signals included.
+#[derive(Debug)]
+pub struct RunLock {
+    _files: Vec<File>,
+}
+
+/// Lock every output path, failing if another run holds one of them unless `wait` is set
+pub fn acquire(paths: &[&str], wait: bool) -> Result<RunLock, SeekerError> {
+    let mut files = Vec::with_capacity(paths.len());
+
+    for path in paths {
+        let lock_path = lock_path(Path::new(path));
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) if wait => {
+                info!(
+                    path,
+                    "Output path is used by another run, waiting for its lock"
+                );
+                file.lock()?;
+            }
+            Err(TryLockError::WouldBlock) => {
+                return Err(SeekerError::Preflight(format!(
+                    "Output path {path} is used by another run (lock {}), use --lock-wait to wait for it",
+                    lock_path.display()
+                )));
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+
+        files.push(file);
+    }
+
+    Ok(RunLock { _files: files })
+}
+
+fn lock_path(path: &Path) -> PathBuf {
+    // Keep the lock of a directory next to it, even when given with a trailing separator
+    let mut lock_path = path.components().as_path().as_os_str().to_owned();
+    lock_path.push(".lock");
+    PathBuf::from(lock_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acquire_locked_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("out");
+        let output = output.to_str().unwrap();
+
+        let lock = acquire(&[output], false).unwrap();
+        assert!(dir.path().join("out.lock").exists());
+        assert!(matches!(
+            acquire(&[output], false),
+            Err(SeekerError::Preflight(_))
+        ));
+        assert!(matches!(
+            acquire(&[&format!("{output}/")], false),
+            Err(SeekerError::Preflight(_))
+        ));
+
+        drop(lock);
+        acquire(&[output], false).unwrap();
+    }
+}
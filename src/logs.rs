@@ -0,0 +1,242 @@
+use crate::error::SeekerError;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use tracing::{debug, warn};
+
+/// jq filter matching the failure events emitted by FoundationDB's consistency checker
+pub const CONSISTENCY_CHECK_FILTER: &str =
+    r#"select((.Type // "") | startswith("ConsistencyCheck")) | select(.Severity=="40")"#;
+
+/// jq filter matching the trace events of at least `severity`, e.g. 40 for the errors
+pub fn severity_filter(severity: u32) -> String {
+    format!(r#"select(((.Severity // "0") | tonumber) >= {severity})"#)
+}
+
+/// Label set on issues reporting a consistency check failure
+pub const CONSISTENCY_CHECK_LABEL: &str = "consistency";
+
+/// Trace events selected from the logs of a simulation
+#[derive(Debug, Default)]
+pub struct FilteredLogs {
+    /// Matching events, pretty-printed one after the other, up to the inline limit
+    pub output: String,
+    /// Number of matching events past the inline limit, left out of `output`
+    pub omitted_events: usize,
+    /// Number of lines skipped because they could not be read or processed by jq
+    pub skipped_lines: usize,
+    /// Number of JSON trace files scanned
+    pub trace_files: usize,
+}
+
+/// Run the jq `filter` over every line of the JSON trace files found in `logs_dir`
+///
+/// Trace output is not always well formed, so lines jq fails on are skipped and counted
+/// rather than aborting the whole report.
+pub fn filter_logs(logs_dir: &Path, filter: &str) -> Result<FilteredLogs, SeekerError> {
+    filter_logs_to(logs_dir, filter, usize::MAX, &mut std::io::sink())
+}
+
+/// Run the jq `filter` as [`filter_logs`] does, streaming every matching event to `full_output`
+///
+/// Only the first `max_inline` events are kept in `output`, so that huge traces do not have to
+/// fit in memory.
+pub fn filter_logs_to(
+    logs_dir: &Path,
+    filter: &str,
+    max_inline: usize,
+    full_output: &mut impl Write,
+) -> Result<FilteredLogs, SeekerError> {
+    let mut compiled = jq_rs::compile(filter)?;
+    let mut events = 0usize;
+
+    let mut filtered_logs = FilteredLogs::default();
+
+    for file in walkdir::WalkDir::new(logs_dir) {
+        let file = file?;
+        if file.path().extension().unwrap_or_default() == "json" {
+            let path = file.path();
+            filtered_logs.trace_files += 1;
+            let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+
+            for (index, line) in reader.lines().enumerate() {
+                let line = match line {
+                    Ok(line) => line,
+                    // Invalid UTF-8 only affects the current line, other IO errors are fatal
+                    Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                        debug!(path = %path.display(), line = index + 1, error = %e, "Skipping unreadable trace line");
+                        filtered_logs.skipped_lines += 1;
+                        continue;
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+                let pretty = match compiled.run(&line) {
+                    Ok(logs) if logs.is_empty() => continue,
+                    Ok(logs) => jsonxf::pretty_print(&logs),
+                    Err(e) => Err(e.to_string()),
+                };
+                match pretty {
+                    Ok(pretty) => {
+                        writeln!(full_output, "{pretty}")?;
+                        if events < max_inline {
+                            filtered_logs.output.push_str(&pretty);
+                            filtered_logs.output.push('\n');
+                        } else {
+                            filtered_logs.omitted_events += 1;
+                        }
+                        events += 1;
+                    }
+                    Err(e) => {
+                        debug!(path = %path.display(), line = index + 1, error = %e, "Skipping malformed trace line");
+                        filtered_logs.skipped_lines += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    full_output.flush()?;
+
+    if filtered_logs.skipped_lines > 0 {
+        warn!(
+            skipped_lines = filtered_logs.skipped_lines,
+            "Some trace lines could not be processed and were skipped"
+        );
+    }
+
+    Ok(filtered_logs)
+}
+
+/// Render a markdown table of the `fields` of the events in `filtered_output`
+///
+/// Events holding none of the fields are left out, `None` is returned when no event holds any.
+pub fn extract_fields(filtered_output: &str, fields: &[String]) -> Option<String> {
+    let mut rows = Vec::new();
+    for event in
+        serde_json::Deserializer::from_str(filtered_output).into_iter::<serde_json::Value>()
+    {
+        let Ok(event) = event else {
+            break;
+        };
+        let cells: Vec<Option<String>> = fields
+            .iter()
+            .map(|field| {
+                event.get(field).map(|value| match value {
+                    serde_json::Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                })
+            })
+            .collect();
+        if cells.iter().any(Option::is_some) {
+            rows.push(cells);
+        }
+    }
+
+    if rows.is_empty() {
+        return None;
+    }
+
+    let escape = |cell: &str| cell.replace('|', "\\|").replace('\n', " ");
+    let mut table = format!(
+        "| {} |\n|{}\n",
+        fields
+            .iter()
+            .map(|field| escape(field))
+            .collect::<Vec<_>>()
+            .join(" | "),
+        " --- |".repeat(fields.len())
+    );
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .map(|cell| cell.as_deref().map(escape).unwrap_or_default())
+            .collect();
+        table.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    Some(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Values of the `N` field of the JSON events of `output`
+    fn event_numbers(output: &str) -> Vec<u64> {
+        serde_json::Deserializer::from_str(output)
+            .into_iter::<serde_json::Value>()
+            .map(|event| event.unwrap()["N"].as_u64().unwrap())
+            .collect()
+    }
+
+    /// Logs directory with a single trace file holding `lines`
+    fn logs_dir(lines: &[&[u8]]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("trace.json"), lines.join(&b'\n')).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_filter_logs_to() {
+        let dir = logs_dir(&[
+            br#"{"Severity":"40","N":1}"#,
+            br#"{"Severity":"10","N":2}"#,
+            br#"{"Severity":"40","N":3"#,
+            b"{\"Severity\":\"40\",\"N\":\xff}",
+            br#"{"Severity":"40","N":4}"#,
+            br#"{"Severity":"40","N":5}"#,
+        ]);
+
+        let mut full_output = Vec::new();
+        let filtered_logs = filter_logs_to(
+            dir.path(),
+            r#"select(.Severity=="40")"#,
+            2,
+            &mut full_output,
+        )
+        .unwrap();
+
+        assert_eq!(filtered_logs.trace_files, 1);
+        assert_eq!(filtered_logs.skipped_lines, 2);
+        assert_eq!(filtered_logs.omitted_events, 1);
+        assert_eq!(event_numbers(&filtered_logs.output), [1, 4]);
+        assert_eq!(
+            event_numbers(&String::from_utf8(full_output).unwrap()),
+            [1, 4, 5]
+        );
+    }
+
+    #[test]
+    fn test_severity_filter() {
+        let dir = logs_dir(&[
+            br#"{"Severity":"10","Time":"0.000000","Type":"ProgramStart","N":1}"#,
+            br#"{"Severity":"30","Time":"12.500000","Type":"TLogPeekError","N":2}"#,
+            br#"{"Severity":"40","Time":"13.250000","Type":"InternalError","N":3}"#,
+        ]);
+
+        let filtered_logs = filter_logs(dir.path(), &severity_filter(40)).unwrap();
+
+        assert_eq!(filtered_logs.skipped_lines, 0);
+        assert_eq!(event_numbers(&filtered_logs.output), [3]);
+    }
+
+    #[test]
+    fn test_extract_fields() {
+        let fields = [
+            "Type".to_string(),
+            "Reason".to_string(),
+            "Machine".to_string(),
+        ];
+        let filtered_output = r#"{"Type":"InternalError","Reason":"a|b"}
+{"Severity":"40"}
+{"Type":"Crash","Machine":"2.0.1.0:1"}
+"#;
+
+        assert_eq!(
+            extract_fields(filtered_output, &fields).unwrap(),
+            "| Type | Reason | Machine |\n\
+             | --- | --- | --- |\n\
+             | InternalError | a\\|b |  |\n\
+             | Crash |  | 2.0.1.0:1 |\n"
+        );
+        assert_eq!(extract_fields(r#"{"Severity":"40"}"#, &fields), None);
+    }
+}
@@ -1,5 +1,12 @@
-use seed_seeker::run;
+use seed_seeker::{RunStatus, run};
 
 fn main() {
-    run().expect("Failed to run");
+    match run() {
+        Ok(status) => std::process::exit(status.exit_code()),
+        Err(e) => {
+            eprintln!("Error: {e}");
+            // Whatever its cause, a failure of the tool is an internal error, not a faulty seed
+            std::process::exit(RunStatus::Errors.exit_code());
+        }
+    }
 }
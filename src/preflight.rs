@@ -0,0 +1,140 @@
+use crate::error::SeekerError;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// What to do when the scratch volume looks too small for the run
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiskSpaceCheck {
+    /// Skip the check
+    Off,
+    /// Log a warning and run anyway
+    Warn,
+    /// Refuse to run
+    Error,
+}
+
+/// Space available to unprivileged users on the volume holding `path`, in bytes
+pub fn available_space(path: &Path) -> std::io::Result<u64> {
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    // SAFETY: an all-zero statvfs is a valid value for the out parameter
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is a valid nul-terminated string and `stat` a valid, writable statvfs struct
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// Check the scratch volume can hold `chunk_size` simulations of `per_seed_mb` each
+pub fn check_disk_space(
+    scratch_dir: &Path,
+    per_seed_mb: u64,
+    chunk_size: usize,
+    check: DiskSpaceCheck,
+) -> Result<(), SeekerError> {
+    if check == DiskSpaceCheck::Off {
+        return Ok(());
+    }
+
+    let required = per_seed_mb * 1024 * 1024 * chunk_size as u64;
+    let available = available_space(scratch_dir)?;
+
+    if available >= required {
+        info!(
+            scratch_dir = %scratch_dir.display(),
+            available_mb = available / 1024 / 1024,
+            required_mb = required / 1024 / 1024,
+            "Enough scratch space for the run"
+        );
+        return Ok(());
+    }
+
+    let message = format!(
+        "Scratch volume of {} has {} MB available but {chunk_size} concurrent seeds of {per_seed_mb} MB need {} MB",
+        scratch_dir.display(),
+        available / 1024 / 1024,
+        required / 1024 / 1024
+    );
+    match check {
+        DiskSpaceCheck::Error => Err(SeekerError::Preflight(message)),
+        _ => {
+            warn!("{message}");
+            Ok(())
+        }
+    }
+}
+
+/// Check that the fdbserver `command`, a path or a name looked up in `PATH`, is an executable file
+///
+/// Returns the resolved path, so that a typo is caught before every seed fails to spawn it.
+pub fn check_fdbserver(command: &str) -> Result<PathBuf, SeekerError> {
+    let is_executable = |path: &Path| {
+        path.metadata()
+            .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+    };
+
+    if !command.contains(std::path::MAIN_SEPARATOR) {
+        return std::env::var_os("PATH")
+            .and_then(|path| {
+                std::env::split_paths(&path)
+                    .map(|dir| dir.join(command))
+                    .find(|path| is_executable(path))
+            })
+            .ok_or_else(|| {
+                SeekerError::Preflight(format!("fdbserver binary `{command}` not found in PATH"))
+            });
+    }
+
+    let path = std::path::absolute(command)?;
+    if !path.exists() {
+        return Err(SeekerError::Preflight(format!(
+            "fdbserver binary {} not found",
+            path.display()
+        )));
+    }
+    if !is_executable(&path) {
+        return Err(SeekerError::Preflight(format!(
+            "fdbserver binary {} is not an executable file",
+            path.display()
+        )));
+    }
+    Ok(path)
+}
+
+/// Short hash of the HEAD commit of the git repository holding the current directory, and
+/// whether its tracked files have uncommitted changes
+pub fn head_commit() -> Result<(String, bool), SeekerError> {
+    let git = |args: &[&str]| -> Result<String, SeekerError> {
+        let output = std::process::Command::new("git").args(args).output()?;
+        if !output.status.success() {
+            return Err(SeekerError::Preflight(format!(
+                "`git {}` failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let commit_id = git(&["rev-parse", "--short", "HEAD"])?;
+    let dirty = !git(&["status", "--porcelain", "--untracked-files=no"])?.is_empty();
+    Ok((commit_id, dirty))
+}
+
+/// Check that the test file at `path` is a readable file, returning its absolute path
+pub fn check_test_file(path: &str) -> Result<PathBuf, SeekerError> {
+    let path = std::path::absolute(path)?;
+    if !path.is_file() {
+        return Err(SeekerError::Preflight(format!(
+            "Test file {} not found",
+            path.display()
+        )));
+    }
+    std::fs::File::open(&path).map_err(|e| {
+        SeekerError::Preflight(format!("Test file {} is not readable: {e}", path.display()))
+    })?;
+    Ok(path)
+}
@@ -0,0 +1,97 @@
+use std::io::Write;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Minimum delay between two redraws of the progress bar
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Width of the bar, in characters
+const BAR_WIDTH: usize = 30;
+
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+/// Progress of the run, redrawn on stderr as a bar or logged as one line per checked seed
+///
+/// The bar shows the rate and elapsed time, along with the ETA when the number of seeds is known.
+/// Without it, a spinner shows the count and rate instead.
+#[derive(Debug)]
+pub struct Progress {
+    total: Option<usize>,
+    bar: bool,
+    started: Instant,
+    drawn_at: Option<Instant>,
+}
+
+impl Progress {
+    pub fn new(total: Option<usize>, bar: bool) -> Self {
+        Self {
+            total,
+            bar,
+            started: Instant::now(),
+            drawn_at: None,
+        }
+    }
+
+    /// Report that `checked` seeds were checked so far
+    pub fn update(&mut self, checked: usize) {
+        if !self.bar {
+            let total = self
+                .total
+                .map_or_else(|| "inf".to_string(), |total| total.to_string());
+            info!("Progress [{checked}/{total}]");
+            return;
+        }
+        let done = self.total == Some(checked);
+        if !done
+            && self
+                .drawn_at
+                .is_some_and(|drawn_at| drawn_at.elapsed() < REDRAW_INTERVAL)
+        {
+            return;
+        }
+        self.drawn_at = Some(Instant::now());
+        // Cleared first so that a shorter line leaves nothing behind
+        let _ = write!(std::io::stderr(), "\r\x1b[2K{}", self.render(checked));
+    }
+
+    /// End the bar, leaving its last state on its own line
+    pub fn finish(&mut self) {
+        if self.bar && self.drawn_at.is_some() {
+            let _ = writeln!(std::io::stderr());
+        }
+    }
+
+    fn render(&self, checked: usize) -> String {
+        let elapsed = self.started.elapsed();
+        let rate = checked as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+        let stats = format!("{rate:.2} seeds/s, elapsed {}", format_duration(elapsed));
+        match self.total {
+            Some(total) => {
+                let filled = (checked * BAR_WIDTH)
+                    .checked_div(total)
+                    .unwrap_or(BAR_WIDTH);
+                let eta = if rate > 0.0 {
+                    let remaining = total.saturating_sub(checked) as f64 / rate;
+                    format_duration(Duration::from_secs_f64(remaining))
+                } else {
+                    "-".to_string()
+                };
+                format!(
+                    "[{}{}] {checked}/{total} {stats}, ETA {eta}",
+                    "=".repeat(filled.min(BAR_WIDTH)),
+                    " ".repeat(BAR_WIDTH - filled.min(BAR_WIDTH))
+                )
+            }
+            None => format!(
+                "{} {checked} seeds {stats}",
+                SPINNER[checked % SPINNER.len()]
+            ),
+        }
+    }
+}
+
+/// Format `duration` as `HH:MM:SS`
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
@@ -0,0 +1,111 @@
+use crate::error::SeekerError;
+use regex::Regex;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use tracing::debug;
+
+const REDACTED: &str = "***";
+
+/// Best-effort masking of secrets in the reported artifacts
+pub struct Redactor {
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    pub fn new(patterns: &[String]) -> Result<Self, regex::Error> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Replace every match of the patterns in `text` by `***`
+    pub fn redact(&self, text: &str) -> String {
+        let mut redacted = text.to_string();
+        for pattern in &self.patterns {
+            redacted = pattern.replace_all(&redacted, REDACTED).into_owned();
+        }
+        redacted
+    }
+
+    /// Redact in place every UTF-8 file found in `dir`, non text files are left untouched
+    pub fn redact_dir(&self, dir: &Path) -> Result<(), SeekerError> {
+        if self.patterns.is_empty() {
+            return Ok(());
+        }
+
+        for file in walkdir::WalkDir::new(dir) {
+            let file = file?;
+            if file.file_type().is_file() {
+                self.redact_file(file.path())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Redact `path` line by line into a temporary file replacing it, so that large trace files
+    /// are never held in memory and an interrupted run never leaves a truncated file behind
+    fn redact_file(&self, path: &Path) -> Result<(), SeekerError> {
+        let mut reader = BufReader::new(std::fs::File::open(path)?);
+        let parent = path.parent().unwrap_or(Path::new("."));
+        let mut redacted = BufWriter::new(tempfile::NamedTempFile::new_in(parent)?);
+        let mut changed = false;
+
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line)? > 0 {
+            let Ok(text) = std::str::from_utf8(&line) else {
+                debug!(path = %path.display(), "Not redacting file which is not UTF-8 text");
+                return Ok(());
+            };
+            let redacted_line = self.redact(text);
+            changed |= redacted_line != text;
+            redacted.write_all(redacted_line.as_bytes())?;
+            line.clear();
+        }
+
+        if changed {
+            let redacted = redacted.into_inner().map_err(|e| e.into_error())?;
+            redacted
+                .as_file()
+                .set_permissions(std::fs::metadata(path)?.permissions())?;
+            redacted.persist(path).map_err(|e| e.error)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs = dir.path().join("logs");
+        std::fs::create_dir(&logs).unwrap();
+        let trace = logs.join("trace.json");
+        std::fs::write(
+            &trace,
+            "{\"Type\":\"Connect\",\"Auth\":\"glpat-abc123XYZ\"}\n{\"Type\":\"Done\"}\n",
+        )
+        .unwrap();
+        let clean = b"{\"Type\":\"Start\"}\r\n\tno secret here \xe2\x9c\x93\n";
+        let clean_file = logs.join("clean.json");
+        std::fs::write(&clean_file, clean).unwrap();
+        let binary = [0xff, 0xfe, b'g', b'l', b'p', b'a', b't', b'-', b'x'];
+        let binary_file = dir.path().join("core");
+        std::fs::write(&binary_file, binary).unwrap();
+
+        let redactor = Redactor::new(&["glpat-[0-9A-Za-z]+".to_string()]).unwrap();
+        redactor.redact_dir(dir.path()).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&trace).unwrap(),
+            "{\"Type\":\"Connect\",\"Auth\":\"***\"}\n{\"Type\":\"Done\"}\n"
+        );
+        assert_eq!(std::fs::read(&clean_file).unwrap(), clean);
+        assert_eq!(std::fs::read(&binary_file).unwrap(), binary);
+    }
+}
@@ -0,0 +1,103 @@
+use crate::gitlab::Payload;
+use crate::reporter::{IssueReporter, ReportedIssue};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::runtime::Runtime;
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Queue shared by the simulation workers, set up once when reports are sent asynchronously
+static REPORT_QUEUE: OnceLock<ReportQueue> = OnceLock::new();
+
+/// Issue reports queued by the simulation workers and sent by a bounded pool of tokio tasks
+///
+/// Workers hand their payload over and move on to the next seed instead of waiting for the
+/// uploads, at most `concurrency` reports being sent at the same time.
+#[derive(Debug)]
+pub struct ReportQueue {
+    runtime: Runtime,
+    permits: Arc<Semaphore>,
+    pending: Mutex<Vec<JoinHandle<Option<ReportedIssue>>>>,
+}
+
+/// Set up the process-wide report queue
+pub fn init(concurrency: usize) -> std::io::Result<()> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    let queue = ReportQueue {
+        runtime,
+        permits: Arc::new(Semaphore::new(concurrency)),
+        pending: Mutex::new(Vec::new()),
+    };
+    if REPORT_QUEUE.set(queue).is_err() {
+        warn!("Report queue already set up");
+    }
+    Ok(())
+}
+
+/// The report queue if reports are sent asynchronously
+pub fn get() -> Option<&'static ReportQueue> {
+    REPORT_QUEUE.get()
+}
+
+impl ReportQueue {
+    /// Queue the issue report of `seed`, `keep_alive` is dropped once the report was sent
+    ///
+    /// `keep_alive` holds what the payload points to on disk, such as the simulation logs.
+    pub fn submit(
+        &self,
+        seed: u32,
+        reporter: Arc<dyn IssueReporter>,
+        payload: Payload,
+        keep_alive: impl Send + 'static,
+    ) {
+        let permits = Arc::clone(&self.permits);
+        let handle = self.runtime.spawn(async move {
+            let Ok(_permit) = permits.acquire_owned().await else {
+                return None;
+            };
+            let result = tokio::task::spawn_blocking(move || {
+                let result = reporter.report(payload).map_err(|e| e.to_string());
+                drop(keep_alive);
+                result
+            })
+            .await;
+            match result {
+                Ok(Ok(issue)) => {
+                    if let ReportedIssue::Created(issue_url) = &issue {
+                        info!(seed, issue_url, "Issue created for faulty seed");
+                    }
+                    Some(issue)
+                }
+                Ok(Err(e)) => {
+                    warn!(seed, error = e, "Failed to create issue for faulty seed");
+                    None
+                }
+                Err(e) => {
+                    warn!(seed, error = ?e, "Report task failed");
+                    None
+                }
+            }
+        });
+        self.pending
+            .lock()
+            .expect("report queue lock poisoned")
+            .push(handle);
+    }
+
+    /// Wait for every queued report to be sent, returning the issues the seeds were reported in
+    pub fn finish(&self) -> Vec<ReportedIssue> {
+        let pending =
+            std::mem::take(&mut *self.pending.lock().expect("report queue lock poisoned"));
+        self.runtime.block_on(async {
+            let mut issues = Vec::new();
+            for handle in pending {
+                if let Ok(Some(issue)) = handle.await {
+                    issues.push(issue);
+                }
+            }
+            issues
+        })
+    }
+}
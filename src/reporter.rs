@@ -0,0 +1,131 @@
+use crate::error::SeekerError;
+use crate::gitlab::Payload;
+use rand::Rng;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Tracker where the faulty seeds are reported
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// GitLab issues, with the artifacts uploaded to the project
+    Gitlab,
+    /// GitHub issues, with the artifacts in a secret gist and release assets
+    Github,
+}
+
+/// Title of the issue filed for `seed`, naming the failing test file when several are run
+pub fn issue_title(kind: &str, seed: u32, test_file: Option<&str>) -> String {
+    match test_file {
+        Some(test_file) => format!("Investigate {kind} #{seed} ({test_file})"),
+        None => format!("Investigate {kind} #{seed}"),
+    }
+}
+
+/// Render the footer appended to the issue descriptions
+///
+/// Always contains the tool version so machine-filed issues are recognizable, preceded by the
+/// user-provided footer if any. Literal `\n` sequences in the user footer are expanded to allow
+/// multiline footers from the command line.
+pub fn render_footer(user_footer: Option<&str>) -> String {
+    let mut footer = String::from("\n---\n");
+    if let Some(user_footer) = user_footer {
+        footer.push_str(&user_footer.replace("\\n", "\n"));
+        footer.push('\n');
+    }
+    footer.push_str(&format!(
+        "_Filed by {} v{}_\n",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    ));
+    footer
+}
+
+/// Issue a faulty seed was reported in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReportedIssue {
+    /// A new issue was filed
+    Created(String),
+    /// The seed already had an open issue, the new logs were added as a comment on it
+    Commented(String),
+}
+
+impl ReportedIssue {
+    /// URL of the issue
+    pub fn url(&self) -> &str {
+        match self {
+            ReportedIssue::Created(url) | ReportedIssue::Commented(url) => url,
+        }
+    }
+}
+
+/// Files an issue, along with its artifacts, for every faulty seed
+pub trait IssueReporter: Send + Sync {
+    /// Claim the report of `seed` on `test_file`, returning `false` if it was already reported
+    /// during the run
+    fn claim_report(&self, seed: u32, test_file: &str) -> bool;
+
+    /// Upload the artifacts of a faulty seed and file an issue for it, or comment on its open one
+    fn report(&self, payload: Payload) -> Result<ReportedIssue, SeekerError>;
+}
+
+/// Seeds already reported during the run, shared by the clones of a reporter handed to the workers
+#[derive(Debug, Clone, Default)]
+pub struct ReportedSeeds(Arc<Mutex<HashSet<(u32, String)>>>);
+
+impl ReportedSeeds {
+    /// Claim the report of `seed` on `test_file`, returning `false` if it was already reported
+    ///
+    /// Looping or retrying runs the same seed several times, a single issue is enough for all of them.
+    pub fn claim(&self, seed: u32, test_file: &str) -> bool {
+        self.0
+            .lock()
+            .expect("reported seeds lock poisoned")
+            .insert((seed, test_file.to_string()))
+    }
+}
+
+/// Delay before the retry following `attempt` failed attempts
+///
+/// Exponential backoff from `base_delay` capped at `max_backoff`, with full jitter: the delay is
+/// drawn uniformly up to the backoff, so workers failing together do not retry in lockstep once
+/// the tracker recovers.
+pub(crate) fn retry_delay(attempt: u32, base_delay: Duration, max_backoff: Duration) -> Duration {
+    let backoff = base_delay
+        .saturating_mul(1 << attempt.min(16))
+        .min(max_backoff);
+    Duration::from_millis(rand::rng().random_range(0..=backoff.as_millis() as u64))
+}
+
+/// Delay requested by the `Retry-After` header of `response`, in seconds
+pub(crate) fn retry_after(response: &reqwest::blocking::Response) -> Option<Duration> {
+    let value = response.headers().get(reqwest::header::RETRY_AFTER)?;
+    Some(Duration::from_secs(
+        value.to_str().ok()?.trim().parse().ok()?,
+    ))
+}
+
+/// Whether the failed attempt of a request that is safe to repeat is worth retrying
+pub(crate) fn is_transient(result: &reqwest::Result<reqwest::blocking::Response>) -> bool {
+    match result {
+        Ok(response) => {
+            response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+                || response.status().is_server_error()
+        }
+        Err(e) => e.is_connect() || e.is_timeout(),
+    }
+}
+
+/// Whether the tracker certainly did not process the failed attempt, so that it can be retried
+///
+/// Used for the requests creating something: after a 500, 502 or a timeout the issue may have
+/// been created anyway, and retrying would file it twice.
+pub(crate) fn is_rejected(result: &reqwest::Result<reqwest::blocking::Response>) -> bool {
+    match result {
+        Ok(response) => matches!(
+            response.status(),
+            reqwest::StatusCode::TOO_MANY_REQUESTS | reqwest::StatusCode::SERVICE_UNAVAILABLE
+        ),
+        Err(e) => e.is_connect(),
+    }
+}
@@ -1,18 +1,99 @@
-use rand::Rng;
-use rand::rngs::ThreadRng;
+use crate::error::SeekerError;
+use rand::rngs::{StdRng, ThreadRng};
+use rand::{Rng, SeedableRng};
+use std::collections::HashSet;
 use std::num::ParseIntError;
+use std::ops::Range;
 
+/// Largest seed accepted by fdbserver, random seeds are drawn up to it included
 pub const MAX_SEED: u32 = u32::MAX;
 
+/// Generator of the random seeds
+enum SeedRng {
+    /// Nondeterministic, a different sequence on every run
+    Thread(ThreadRng),
+    /// Seeded, the same sequence for the same seed
+    Seeded(Box<StdRng>),
+}
+
+impl SeedRng {
+    fn random_seed(&mut self, max_seed: u32) -> u32 {
+        match self {
+            SeedRng::Thread(rng) => rng.random_range(0..=max_seed),
+            SeedRng::Seeded(rng) => rng.random_range(0..=max_seed),
+        }
+    }
+}
+
 pub struct SeedIterator {
     seeds: Option<Vec<u32>>,
-    rng: ThreadRng,
+    /// Seeds to start over with once `seeds` is exhausted, when looping
+    looped_seeds: Option<Vec<u32>>,
+    /// Contiguous seeds enumerated in ascending order instead of random ones
+    range: Option<Range<u32>>,
+    /// Number of random seeds left to generate, unbounded if not set
+    random_count: Option<usize>,
+    rng: SeedRng,
+    max_seed: u32,
+    /// Random seeds already generated, to draw every seed at most once
+    generated: Option<HashSet<u32>>,
 }
 
 impl SeedIterator {
     pub fn new(seeds: Option<Vec<u32>>) -> Self {
-        let rng = rand::rng();
-        Self { seeds, rng }
+        let rng = SeedRng::Thread(rand::rng());
+        Self {
+            seeds,
+            looped_seeds: None,
+            range: None,
+            random_count: None,
+            rng,
+            max_seed: MAX_SEED,
+            generated: None,
+        }
+    }
+
+    /// Yield every seed of `range` once, in ascending order
+    pub fn from_range(range: Range<u32>) -> Self {
+        Self {
+            range: Some(range),
+            ..Self::new(None)
+        }
+    }
+
+    /// Yield exactly `count` random seeds
+    pub fn random(count: usize) -> Self {
+        Self {
+            random_count: Some(count),
+            ..Self::new(None)
+        }
+    }
+
+    /// Cycle through the provided seeds indefinitely instead of stopping once they are exhausted
+    pub fn looping(mut self) -> Self {
+        self.looped_seeds = self.seeds.clone();
+        self
+    }
+
+    /// Generate the random seeds from `rng_seed`, so that a random sweep can be reproduced
+    pub fn with_rng_seed(mut self, rng_seed: u64) -> Self {
+        self.rng = SeedRng::Seeded(Box::new(StdRng::seed_from_u64(rng_seed)));
+        self
+    }
+
+    /// Never generate the same random seed twice
+    ///
+    /// The seeds drawn are remembered, so the memory used grows with the number of seeds. The
+    /// iterator ends once every seed up to the max seed was drawn.
+    pub fn unique(mut self) -> Self {
+        self.generated = Some(HashSet::new());
+        self
+    }
+
+    /// Only generate random seeds up to `max_seed` included
+    pub fn with_max_seed(mut self, max_seed: u32) -> Self {
+        self.max_seed = max_seed;
+        self
     }
 }
 
@@ -21,28 +102,116 @@ impl Iterator for SeedIterator {
 
     fn next(&mut self) -> Option<Self::Item> {
         if let Some(ref mut seeds) = self.seeds {
+            if seeds.is_empty()
+                && let Some(looped_seeds) = &self.looped_seeds
+            {
+                seeds.clone_from(looped_seeds);
+            }
             return seeds.pop();
         }
+        if let Some(range) = &mut self.range {
+            return range.next();
+        }
+        if let Some(random_count) = &mut self.random_count {
+            *random_count = random_count.checked_sub(1)?;
+        }
 
-        Some(self.rng.random_range(0..MAX_SEED))
+        let Some(generated) = &mut self.generated else {
+            return Some(self.rng.random_seed(self.max_seed));
+        };
+        if generated.len() as u64 > u64::from(self.max_seed) {
+            return None;
+        }
+        loop {
+            let seed = self.rng.random_seed(self.max_seed);
+            if generated.insert(seed) {
+                return Some(seed);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match (&self.seeds, &self.range) {
+            (Some(_), _)
+                if self
+                    .looped_seeds
+                    .as_ref()
+                    .is_some_and(|seeds| !seeds.is_empty()) =>
+            {
+                (usize::MAX, None)
+            }
+            (Some(seeds), _) => (seeds.len(), Some(seeds.len())),
+            (None, Some(range)) => range.size_hint(),
+            (None, None) => match self.random_count {
+                Some(random_count) => (random_count, Some(random_count)),
+                None => (usize::MAX, None),
+            },
+        }
     }
 }
 
+/// Largest number of seeds of an `A-B` line of a seed file, which are all held in memory
+///
+/// `--seed-range` sweeps larger ranges without holding them.
+pub const MAX_SEED_FILE_RANGE: u64 = 1_000_000;
+
 /// Parse seeds from a file
 /// Read line per line the provided file and extract seeds from it
-pub fn parse_seeds_file(path: &str) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>> {
-    let file = std::fs::read_to_string(path)?;
-    let seeds: Vec<u32> = file
-        .lines()
-        .map(|line| line.parse())
-        .collect::<Result<_, ParseIntError>>()?;
+/// A leading UTF-8 BOM and the whitespace around each seed (including the `\r` of CRLF line
+/// endings) are ignored, as files written on Windows or by some editors have them.
+/// Blank lines and lines starting with `#` are skipped, and an `A-B` line expands to the seeds
+/// from `A` to `B` included.
+/// A `-` path reads the seeds from the standard input until its end, to pipe seeds in.
+pub fn parse_seeds_file(path: &str) -> Result<Option<Vec<u32>>, SeekerError> {
+    if path == "-" {
+        let mut input = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut input)?;
+        return parse_seeds(&input, "<stdin>");
+    }
+    parse_seeds(&std::fs::read_to_string(path)?, path)
+}
 
-    for seed in &seeds {
-        if seed > &MAX_SEED {
-            return Err(format!("Seed {} is greater than {}", seed, MAX_SEED).into());
+/// Parse the seeds of a seed file read from `source`, see [`parse_seeds_file`]
+fn parse_seeds(file: &str, source: &str) -> Result<Option<Vec<u32>>, SeekerError> {
+    let mut seeds = Vec::new();
+    for (index, line) in file
+        .strip_prefix('\u{feff}')
+        .unwrap_or(file)
+        .lines()
+        .enumerate()
+    {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line_number = index + 1;
+        let invalid = |e: ParseIntError| {
+            SeekerError::SeedParse(format!(
+                "{source}:{line_number}: invalid seed `{line}`: {e}"
+            ))
+        };
+        match line.split_once('-') {
+            Some((start, end)) => {
+                let start: u32 = start.trim().parse().map_err(invalid)?;
+                let end: u32 = end.trim().parse().map_err(invalid)?;
+                if start > end {
+                    return Err(SeekerError::SeedParse(format!(
+                        "{source}:{line_number}: empty seed range `{line}`"
+                    )));
+                }
+                if u64::from(end - start) + 1 > MAX_SEED_FILE_RANGE {
+                    return Err(SeekerError::SeedParse(format!(
+                        "{source}:{line_number}: seed range `{line}` holds more than {MAX_SEED_FILE_RANGE} seeds, use --seed-range to sweep it"
+                    )));
+                }
+                seeds.extend(start..=end);
+            }
+            None => seeds.push(line.parse().map_err(invalid)?),
         }
     }
 
+    // Seeds above u32::MAX already fail to parse, those above `--seed-max` are rejected along
+    // with the seeds of `--seeds` by `check_seed_range`
     Ok(Some(seeds))
 }
 
@@ -61,13 +230,15 @@ pub fn parse_seeds_file(path: &str) -> Result<Option<Vec<u32>>, Box<dyn std::err
 /// * `seeds` - An `Option<Vec<u32>>` containing user-defined seeds. Can be `None` if no seeds are provided by the user.
 /// * `file_seeds_path` - A reference to an `Option<String>` specifying the path to a file containing seeds.
 ///   If `None`, no file-based seeds are loaded.
+/// * `dedup` - Whether to drop the seeds already seen, so that the same seed is not simulated (and
+///   reported) several times.
 ///
 /// # Returns
 ///
 /// Returns a `Result` containing:
 /// * `Ok(Some(Vec<u32>))` - If any seeds (user-defined or file-based) are successfully merged.
 /// * `Ok(None)` - If no seeds are provided by either source.
-/// * `Err(Box<dyn std::error::Error>)` - If an error occurs while parsing the file specified by `file_seeds_path`.
+/// * `Err(SeekerError)` - If an error occurs while parsing the file specified by `file_seeds_path`.
 ///
 /// # Errors
 ///
@@ -83,11 +254,13 @@ pub fn parse_seeds_file(path: &str) -> Result<Option<Vec<u32>>, Box<dyn std::err
 /// # Notes
 ///
 /// The order of seeds in the resulting `Vec<u32>` will maintain the order of `seeds` first,
-/// followed by the order of seeds from the file (if any).
+/// followed by the order of seeds from the file (if any). When deduplicating, the first occurrence
+/// of each seed is kept.
 pub fn merge_user_defined_seeds(
     seeds: Option<Vec<u32>>,
     file_seeds_path: &Option<String>,
-) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>> {
+    dedup: bool,
+) -> Result<Option<Vec<u32>>, SeekerError> {
     let file_seeds = match file_seeds_path {
         Some(path) => parse_seeds_file(path)?,
         None => None,
@@ -104,12 +277,107 @@ pub fn merge_user_defined_seeds(
         None => file_seeds,
     };
 
+    if dedup {
+        let mut seen = HashSet::new();
+        return Ok(seeds.map(|seeds| {
+            seeds
+                .into_iter()
+                .filter(|seed| seen.insert(*seed))
+                .collect()
+        }));
+    }
+
     Ok(seeds)
 }
 
+/// Parse a `START..END` seed range, END excluded
+pub fn parse_seed_range(range: &str) -> Result<Range<u32>, String> {
+    let (start, end) = range
+        .split_once("..")
+        .ok_or_else(|| format!("expected START..END, got `{range}`"))?;
+    let start: u32 = start
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid range start `{start}`: {e}"))?;
+    let end: u32 = end
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid range end `{end}`: {e}"))?;
+    if start >= end {
+        return Err(format!(
+            "range start {start} must be lower than its end {end}"
+        ));
+    }
+    Ok(start..end)
+}
+
+/// Reject the user-defined seeds above `max_seed`, which the fdbserver binary would not accept
+pub fn check_seed_range(seeds: &[u32], max_seed: u32) -> Result<(), SeekerError> {
+    let out_of_range: Vec<u32> = seeds
+        .iter()
+        .copied()
+        .filter(|seed| *seed > max_seed)
+        .collect();
+    if !out_of_range.is_empty() {
+        return Err(SeekerError::SeedParse(format!(
+            "Seeds {out_of_range:?} are greater than the maximum seed {max_seed} (see --seed-max)"
+        )));
+    }
+    Ok(())
+}
+
+/// Whether `seed` belongs to the deterministic 1-in-`every` sample of the seed space
+///
+/// The selection only depends on the seed value, so the same seeds are sampled whatever
+/// their order or source, run after run.
+pub fn is_sampled(seed: u32, every: u32) -> bool {
+    // Mix the bits (murmur3 finalizer) so that the sample does not follow the seed parity
+    let mut hash = seed;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^= hash >> 16;
+    hash.is_multiple_of(every.max(1))
+}
+
+/// Exploration of the seed space computed from seed records
+#[derive(Debug)]
+pub struct Coverage {
+    /// Number of distinct seeds tested
+    pub distinct_seeds: usize,
+    /// Number of seeds in the whole seed space
+    pub space_size: u64,
+}
+
+impl Coverage {
+    pub fn from_seeds(seeds: impl IntoIterator<Item = u32>) -> Self {
+        let distinct: HashSet<u32> = seeds.into_iter().collect();
+        Self {
+            distinct_seeds: distinct.len(),
+            space_size: MAX_SEED as u64 + 1,
+        }
+    }
+
+    /// Build the coverage from several seed record files, each parsed as a seed file
+    pub fn from_files(paths: &[String]) -> Result<Self, SeekerError> {
+        let mut seeds = Vec::new();
+        for path in paths {
+            seeds.extend(parse_seeds_file(path)?.unwrap_or_default());
+        }
+        Ok(Self::from_seeds(seeds))
+    }
+
+    /// Fraction of the seed space explored, between 0 and 1
+    pub fn ratio(&self) -> f64 {
+        self.distinct_seeds as f64 / self.space_size as f64
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::Write;
     #[test]
     fn test_seed_iterator() {
         let seeds = vec![1, 2, 3];
@@ -127,4 +395,188 @@ mod tests {
             println!("{}", i);
         }
     }
+
+    #[test]
+    fn test_seed_iterator_looping() {
+        let iter = SeedIterator::new(Some(vec![1, 2])).looping();
+        assert_eq!(iter.take(5).collect::<Vec<_>>(), vec![2, 1, 2, 1, 2]);
+
+        let mut iter = SeedIterator::new(Some(vec![])).looping();
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_seed_iterator_max_seed() {
+        let iter = SeedIterator::new(None).with_max_seed(3);
+        assert!(iter.take(100).all(|seed| seed <= 3));
+        assert!(check_seed_range(&[0, 3], 3).is_ok());
+        assert!(matches!(
+            check_seed_range(&[0, 4], 3),
+            Err(SeekerError::SeedParse(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_seeds_file_bom_crlf() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all("\u{feff}12\r\n 34 \r\n56\r\n".as_bytes())
+            .unwrap();
+
+        let seeds = parse_seeds_file(&file.path().to_string_lossy()).unwrap();
+        assert_eq!(seeds, Some(vec![12, 34, 56]));
+    }
+
+    #[test]
+    fn test_parse_seeds_file_comments_and_ranges() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"# regression batch\n7\n\n1000-1003\n  # flaky\n42 - 43\n5-5\n")
+            .unwrap();
+
+        let seeds = parse_seeds_file(&file.path().to_string_lossy()).unwrap();
+        assert_eq!(seeds, Some(vec![7, 1000, 1001, 1002, 1003, 42, 43, 5]));
+    }
+
+    #[test]
+    fn test_parse_seeds_file_malformed_line() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"1\n# ok\n12a\n").unwrap();
+        let path = file.path().to_string_lossy().to_string();
+
+        let error = parse_seeds_file(&path).unwrap_err().to_string();
+        assert!(
+            error.starts_with(&format!("{path}:3: invalid seed `12a`")),
+            "{error}"
+        );
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"10-2\n").unwrap();
+        assert!(matches!(
+            parse_seeds_file(&file.path().to_string_lossy()),
+            Err(SeekerError::SeedParse(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_seeds_file_oversized_range() {
+        // Expanded eagerly, this line alone would take 16 GiB
+        assert!(matches!(
+            parse_seeds("0-4294967295\n", "seeds.txt"),
+            Err(SeekerError::SeedParse(message)) if message.contains("more than 1000000 seeds")
+        ));
+
+        let last = MAX_SEED_FILE_RANGE as u32 - 1;
+        let seeds = parse_seeds(&format!("0-{last}\n"), "seeds.txt")
+            .unwrap()
+            .unwrap();
+        assert_eq!(seeds.len() as u64, MAX_SEED_FILE_RANGE);
+        assert!(parse_seeds(&format!("0-{}\n", last + 1), "seeds.txt").is_err());
+    }
+
+    #[test]
+    fn test_merge_user_defined_seeds_dedup() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "7\n5\n8\n7").unwrap();
+        let path = Some(file.path().to_string_lossy().into_owned());
+
+        let merged = merge_user_defined_seeds(Some(vec![5, 3, 5]), &path, true).unwrap();
+        assert_eq!(merged, Some(vec![5, 3, 7, 8]));
+
+        let merged = merge_user_defined_seeds(Some(vec![5, 3, 5]), &path, false).unwrap();
+        assert_eq!(merged, Some(vec![5, 3, 5, 7, 5, 8, 7]));
+
+        let merged = merge_user_defined_seeds(None, &path, true).unwrap();
+        assert_eq!(merged, Some(vec![7, 5, 8]));
+
+        assert_eq!(merge_user_defined_seeds(None, &None, true).unwrap(), None);
+    }
+
+    #[test]
+    fn test_seed_iterator_range() {
+        let iter = SeedIterator::from_range(parse_seed_range("1000..1005").unwrap());
+        assert_eq!(iter.size_hint(), (5, Some(5)));
+        assert_eq!(iter.collect::<Vec<_>>(), vec![1000, 1001, 1002, 1003, 1004]);
+        assert_eq!(
+            SeedIterator::from_range(10..20).take(3).collect::<Vec<_>>(),
+            vec![10, 11, 12]
+        );
+
+        assert!(parse_seed_range("2000..1000").is_err());
+        assert!(parse_seed_range("1000..1000").is_err());
+        assert!(parse_seed_range("1000").is_err());
+        assert!(parse_seed_range("a..10").is_err());
+    }
+
+    #[test]
+    fn test_seed_iterator_random_count() {
+        let iter = SeedIterator::random(5).with_max_seed(10);
+        assert_eq!(iter.size_hint(), (5, Some(5)));
+        let seeds: Vec<u32> = iter.collect();
+        assert_eq!(seeds.len(), 5);
+        assert!(seeds.iter().all(|seed| *seed <= 10));
+
+        assert_eq!(SeedIterator::random(0).next(), None);
+    }
+
+    #[test]
+    fn test_seed_iterator_rng_seed() {
+        let seeds = |rng_seed| {
+            SeedIterator::random(5)
+                .with_rng_seed(rng_seed)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(seeds(42), seeds(42));
+        assert_ne!(seeds(42), seeds(43));
+    }
+
+    #[test]
+    fn test_seed_iterator_unique() {
+        let seeds: Vec<_> = SeedIterator::new(None)
+            .with_max_seed(999)
+            .with_rng_seed(7)
+            .unique()
+            .take(2000)
+            .collect();
+
+        assert_eq!(seeds.len(), 1000);
+        assert_eq!(seeds.iter().collect::<HashSet<_>>().len(), 1000);
+    }
+
+    #[test]
+    fn test_parse_seeds_file_max_seed() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"4294967295\n4294967290-4294967295\n")
+            .unwrap();
+        let seeds = parse_seeds_file(&file.path().to_string_lossy())
+            .unwrap()
+            .unwrap();
+        assert_eq!(seeds.len(), 7);
+        assert!(seeds.iter().all(|seed| *seed >= 4294967290));
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"4294967296\n").unwrap();
+        assert!(parse_seeds_file(&file.path().to_string_lossy()).is_err());
+    }
+
+    #[test]
+    fn test_is_sampled() {
+        assert!((0..1000).all(|seed| is_sampled(seed, 1)));
+
+        let sampled: Vec<u32> = (0..100_000).filter(|seed| is_sampled(*seed, 100)).collect();
+        assert!((800..1200).contains(&sampled.len()));
+        assert_eq!(
+            sampled,
+            (0..100_000)
+                .filter(|seed| is_sampled(*seed, 100))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_coverage_counts_distinct_seeds() {
+        let coverage = Coverage::from_seeds(vec![1, 2, 2, 3, 3, 3]);
+        assert_eq!(coverage.distinct_seeds, 3);
+        assert_eq!(coverage.space_size, 1 << 32);
+        assert!(coverage.ratio() > 0.0);
+    }
 }
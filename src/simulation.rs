@@ -0,0 +1,1038 @@
+use crate::Cli;
+use crate::error::SeekerError;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use subprocess::{ExitStatus, PopenConfig, Redirection};
+use tempfile::TempDir;
+use tracing::{info, trace, warn};
+
+/// Buggify mode of the simulations, passed to fdbserver with `-b`
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Buggify {
+    /// Buggify enabled
+    On,
+    /// Buggify disabled
+    Off,
+    /// Let fdbserver pick, for builds supporting `-b random`
+    Random,
+}
+
+impl Buggify {
+    /// Value of the fdbserver `-b` argument
+    fn as_arg(self) -> &'static str {
+        match self {
+            Buggify::On => "on",
+            Buggify::Off => "off",
+            Buggify::Random => "random",
+        }
+    }
+}
+
+/// Confirmation re-runs of a faulty seed which must reproduce the failure for it to be reported
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmMode {
+    /// At least one re-run
+    Any,
+    /// Every re-run
+    All,
+}
+
+/// Set when the run stops early, the running simulations are then killed
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Set when the run was interrupted by SIGINT or SIGTERM
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Interval at which running simulations check whether the run is stopping
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How long the output pipes may stay open once fdbserver exited
+///
+/// A process forked by fdbserver can inherit the pipes and keep them open, the output is then
+/// given up rather than blocking the worker until that process exits.
+const PIPE_CLOSE_GRACE: Duration = Duration::from_secs(2);
+
+/// Kill the running simulations and the ones started afterwards, the run is stopping
+pub fn cancel_all() {
+    CANCELLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether the run is stopping, no more seeds are to be started
+pub fn is_cancelled() -> bool {
+    CANCELLED.load(Ordering::Relaxed)
+}
+
+/// Whether the run was interrupted by SIGINT or SIGTERM
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+extern "C" fn on_stop_signal(_signal: libc::c_int) {
+    // Only async-signal-safe operations here, the workers and simulations poll the flags
+    INTERRUPTED.store(true, Ordering::Relaxed);
+    CANCELLED.store(true, Ordering::Relaxed);
+}
+
+/// Stop the run on SIGINT and SIGTERM, terminating the running simulations
+///
+/// The handlers are reset once triggered, so a second Ctrl-C kills the tool right away.
+pub fn install_signal_handlers() -> std::io::Result<()> {
+    for signal in [libc::SIGINT, libc::SIGTERM] {
+        // SAFETY: an all-zero sigaction is a valid value, the fields used are set below
+        let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+        action.sa_sigaction = on_stop_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESETHAND | libc::SA_RESTART;
+        // SAFETY: `action` is a valid sigaction whose handler only stores to atomics
+        if unsafe { libc::sigaction(signal, &action, std::ptr::null_mut()) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Temporary directory tree a simulation runs in, removed when dropped
+///
+/// fdbserver has no mode to be fed several seeds, so a process is spawned per seed; preparing
+/// the workspace of the next seed while the current ones run keeps this setup off the critical path.
+pub struct Workspace {
+    data_dir: TempDir,
+}
+
+impl Workspace {
+    pub fn prepare() -> std::io::Result<Self> {
+        let data_dir = tempfile::tempdir()?;
+        std::fs::create_dir_all(data_dir.path().join("simfdb"))?;
+        std::fs::create_dir_all(data_dir.path().join("logs"))?;
+        Ok(Self { data_dir })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.data_dir.path()
+    }
+
+    pub fn logs_dir(&self) -> PathBuf {
+        self.path().join("logs")
+    }
+}
+
+/// Outcome of a single fdbserver simulation run
+pub struct Simulation {
+    /// Workspace holding the simfdb data and the trace logs,
+    /// removed when the simulation is dropped
+    workspace: Workspace,
+    /// Exit status of fdbserver, `None` if the simulation timed out
+    pub exit_status: Option<ExitStatus>,
+    /// raw stdout output
+    pub stdout: Option<String>,
+    /// raw stderr output
+    pub stderr: Option<String>,
+    /// core dump written by fdbserver when it was killed by a signal
+    pub core_dump: Option<PathBuf>,
+    /// fdbserver binary that ran the simulation
+    pub fdbserver_path: String,
+    /// snapshot of the hung process taken before terminating it, if requested
+    pub timeout_diagnostics: Option<String>,
+    /// output of the post-seed validation command if it failed
+    pub validation_failure: Option<String>,
+}
+
+impl Simulation {
+    pub fn logs_dir(&self) -> PathBuf {
+        self.workspace.logs_dir()
+    }
+
+    /// Directory of the workspace, removed with the simulation
+    pub fn workspace_dir(&self) -> &Path {
+        self.workspace.path()
+    }
+
+    /// Directory holding the on-disk simfdb state
+    pub fn data_dir(&self) -> PathBuf {
+        self.workspace.path().join("simfdb")
+    }
+
+    /// Copy the data and logs directories of the simulation to `dir/seed_<seed>`, creating it if needed
+    ///
+    /// The workspace itself is still removed when the simulation is dropped.
+    pub fn keep_artifacts(&self, dir: &Path, seed: u32) -> Result<PathBuf, SeekerError> {
+        let destination = dir.join(format!("seed_{seed}"));
+        for entry in walkdir::WalkDir::new(self.workspace.path()) {
+            let entry = entry?;
+            let target = destination.join(entry.path().strip_prefix(self.workspace.path())?);
+            if entry.file_type().is_dir() {
+                std::fs::create_dir_all(&target)?;
+            } else if entry.file_type().is_file() {
+                std::fs::copy(entry.path(), &target)?;
+            }
+        }
+        Ok(destination)
+    }
+
+    /// Whether fdbserver was terminated after reaching the timeout
+    pub fn timed_out(&self) -> bool {
+        self.exit_status.is_none()
+    }
+
+    /// Whether fdbserver finished within the timeout with a failure exit status
+    pub fn is_faulty(&self) -> bool {
+        matches!(self.exit_status, Some(exit_status) if !exit_status.success())
+    }
+
+    /// Run the shell `command` with the data directory as argument, e.g. an external invariant checker
+    ///
+    /// On failure, the exit status and output of the command are kept in `validation_failure`.
+    /// Returns whether the validation passed.
+    pub fn validate(&mut self, command: &str) -> std::io::Result<bool> {
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("{command} \"$1\""))
+            .arg("sh")
+            .arg(self.data_dir())
+            .output()?;
+        if output.status.success() {
+            return Ok(true);
+        }
+
+        self.validation_failure = Some(format!(
+            "{}\nstdout:\n{}\nstderr:\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+        Ok(false)
+    }
+}
+
+/// Run the simulation of `seed` with the given fdbserver binary in `workspace`
+///
+/// `extra_args` are passed to fdbserver after the regular arguments, before the seed.
+/// On timeout the process is terminated and the returned simulation has no exit status,
+/// its partial output is only kept when timeouts are treated as failures.
+pub fn simulate(
+    fdbserver_path: &str,
+    seed: u32,
+    cli: &Cli,
+    workspace: Workspace,
+    extra_args: &[String],
+) -> Result<Simulation, SeekerError> {
+    let (command, test_file) = command_paths(fdbserver_path, cli)?;
+    let cwd = cli
+        .collect_core_dumps
+        .then(|| workspace.path().as_os_str().to_owned());
+
+    let config = if cli.no_capture_output {
+        // Discarded rather than inherited, the output of parallel simulations would interleave
+        PopenConfig {
+            stdout: Redirection::File(std::fs::File::create("/dev/null")?),
+            stderr: Redirection::File(std::fs::File::create("/dev/null")?),
+            cwd,
+            ..Default::default()
+        }
+    } else {
+        PopenConfig {
+            stdout: Redirection::Pipe,
+            stderr: Redirection::Pipe,
+            cwd,
+            ..Default::default()
+        }
+    };
+
+    let argv = fdbserver_argv(
+        &command,
+        &test_file,
+        workspace.path(),
+        seed,
+        cli,
+        extra_args,
+    );
+
+    trace!(seed, argv = argv.join(" "), "Running fdbserver");
+    let mut process = subprocess::Popen::create(argv.as_slice(), config)?;
+    // Drain the pipes while waiting, fdbserver would otherwise block on a full pipe buffer
+    let stdout_reader = drain(process.stdout.take());
+    let stderr_reader = drain(process.stderr.take());
+
+    // Wait in short steps, so that a stopping run does not leave fdbserver processes behind
+    let deadline = Instant::now() + Duration::from_secs(cli.timeout_secs());
+    let waited = loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match process.wait_timeout(remaining.min(CANCEL_POLL_INTERVAL)) {
+            Ok(None) if is_cancelled() => {
+                warn!(seed, "Run stopping; terminating simulation");
+                if let Err(e) = process.terminate() {
+                    warn!(seed, error = ?e, "Failed to terminate process");
+                }
+                if process
+                    .wait_timeout(Duration::from_secs(cli.flush_window_secs))?
+                    .is_none()
+                {
+                    warn!(
+                        seed,
+                        "Simulation still running after termination; killing it"
+                    );
+                    if let Err(e) = process.kill() {
+                        warn!(seed, error = ?e, "Failed to kill process");
+                    }
+                    process.wait()?;
+                }
+                return Err(SeekerError::Interrupted(
+                    "simulation cancelled, the run is stopping".to_string(),
+                ));
+            }
+            Ok(None) if !remaining.is_zero() => continue,
+            waited => break waited,
+        }
+    };
+
+    match waited {
+        Ok(Some(exit_status)) => {
+            // Process finished within timeout; collect the output drained so far
+            let pipes_deadline = Instant::now() + PIPE_CLOSE_GRACE;
+            let stdout = collect(stdout_reader, pipes_deadline);
+            let stderr = collect(stderr_reader, pipes_deadline);
+            let core_dump = match exit_status {
+                ExitStatus::Signaled(_) if cli.collect_core_dumps => {
+                    let core_dump = find_core_dump(workspace.path())?;
+                    if core_dump.is_none() {
+                        warn!(
+                            seed,
+                            "fdbserver was killed by a signal but no core dump was found"
+                        );
+                    }
+                    core_dump
+                }
+                _ => None,
+            };
+            Ok(Simulation {
+                workspace,
+                exit_status: Some(exit_status),
+                stdout,
+                stderr,
+                core_dump,
+                fdbserver_path: fdbserver_path.to_string(),
+                timeout_diagnostics: None,
+                validation_failure: None,
+            })
+        }
+        Ok(None) => {
+            // Timed out
+            warn!(
+                seed,
+                timeout_secs = cli.timeout_secs(),
+                "Timeout reached; terminating process and continuing"
+            );
+            let timeout_diagnostics = cli
+                .timeout_diagnostics
+                .then(|| hang_snapshot(process.pid(), &workspace.logs_dir()));
+            // SIGTERM first, giving fdbserver a chance to flush its trace buffers
+            if let Err(e) = process.terminate() {
+                warn!(seed, error = ?e, "Failed to terminate process");
+            }
+            match process.wait_timeout(Duration::from_secs(cli.flush_window_secs)) {
+                Ok(Some(_)) => info!(seed, "Timed-out process exited on SIGTERM"),
+                Ok(None) => {
+                    warn!(
+                        seed,
+                        flush_window_secs = cli.flush_window_secs,
+                        "Process still running after the flush window; killing it"
+                    );
+                    if let Err(e) = process.kill() {
+                        warn!(seed, error = ?e, "Failed to kill process");
+                    }
+                    process.wait()?;
+                    info!(seed, "Timed-out process reaped after SIGKILL");
+                }
+                Err(e) => warn!(seed, error = ?e, "Error while waiting for process to exit"),
+            }
+            // Keep the output written before the timeout when the seed is reported as faulty
+            let pipes_deadline = Instant::now() + PIPE_CLOSE_GRACE;
+            let (stdout, stderr) = (
+                collect(stdout_reader, pipes_deadline),
+                collect(stderr_reader, pipes_deadline),
+            );
+            let (stdout, stderr) = if cli.timeout_is_failure {
+                (stdout, stderr)
+            } else {
+                (None, None)
+            };
+            // Do not treat as error; continue with next seeds
+            Ok(Simulation {
+                workspace,
+                exit_status: None,
+                stdout,
+                stderr,
+                core_dump: None,
+                fdbserver_path: fdbserver_path.to_string(),
+                timeout_diagnostics,
+                validation_failure: None,
+            })
+        }
+        Err(e) => {
+            // An actual error while waiting; try to terminate and bubble up the error
+            warn!(seed, error = ?e, "Error while waiting for process; terminating");
+            if let Err(e2) = process.terminate() {
+                warn!(seed, error = ?e2, "Failed to terminate process");
+            }
+            Err(e.into())
+        }
+    }
+}
+
+/// Read `pipe` to its end in a background thread, returning the accumulated output once joined
+fn drain(pipe: Option<std::fs::File>) -> Option<std::thread::JoinHandle<String>> {
+    pipe.map(|mut pipe| {
+        std::thread::spawn(move || {
+            let mut output = Vec::new();
+            if let Err(e) = pipe.read_to_end(&mut output) {
+                warn!(error = ?e, "Failed to read simulation output");
+            }
+            String::from_utf8_lossy(&output).into_owned()
+        })
+    })
+}
+
+/// Wait for the output drained by `reader`, available once the process closed the pipe
+///
+/// Gives up at `deadline` when the pipe is still open, leaving the reader thread to finish
+/// whenever the last process holding the pipe exits.
+fn collect(reader: Option<std::thread::JoinHandle<String>>, deadline: Instant) -> Option<String> {
+    let reader = reader?;
+    while !reader.is_finished() {
+        if Instant::now() >= deadline {
+            warn!("Simulation output still open after fdbserver exited, dropping it");
+            return None;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    Some(reader.join().unwrap_or_default())
+}
+
+/// Number of trace events kept in the snapshot of a hung simulation
+const SNAPSHOT_TRACE_EVENTS: usize = 20;
+
+/// Describe the state of the hung fdbserver process `pid` and its last trace events
+///
+/// Taken from `/proc` before the process is terminated, so the snapshot shows where it was stuck.
+fn hang_snapshot(pid: Option<u32>, logs_dir: &Path) -> String {
+    let mut snapshot = String::new();
+
+    if let Some(pid) = pid {
+        let proc_dir = PathBuf::from(format!("/proc/{pid}"));
+        if let Ok(status) = std::fs::read_to_string(proc_dir.join("status")) {
+            for line in status.lines().filter(|line| {
+                ["State:", "Threads:", "VmRSS:", "voluntary_ctxt_switches:"]
+                    .iter()
+                    .any(|key| line.starts_with(key))
+            }) {
+                snapshot.push_str(line);
+                snapshot.push('\n');
+            }
+        }
+        if let Ok(wchan) = std::fs::read_to_string(proc_dir.join("wchan")) {
+            snapshot.push_str(&format!("Waiting in: {wchan}\n"));
+        }
+    }
+
+    // The most recently written trace file holds the last events before the hang
+    let last_trace_file = walkdir::WalkDir::new(logs_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().unwrap_or_default() == "json")
+        .max_by_key(|entry| {
+            entry
+                .metadata()
+                .ok()
+                .and_then(|metadata| metadata.modified().ok())
+        });
+    if let Some(last_trace_file) = last_trace_file
+        && let Ok(traces) = std::fs::read_to_string(last_trace_file.path())
+    {
+        let lines: Vec<&str> = traces.lines().collect();
+        snapshot.push_str(&format!(
+            "Last trace events of {}:\n",
+            last_trace_file.file_name().to_string_lossy()
+        ));
+        for line in &lines[lines.len().saturating_sub(SNAPSHOT_TRACE_EVENTS)..] {
+            snapshot.push_str(line);
+            snapshot.push('\n');
+        }
+    }
+
+    snapshot
+}
+
+/// Paths of the fdbserver binary and of the test file as passed to fdbserver
+///
+/// Core files are written in the working directory of the crashing process, so when collecting
+/// them each simulation runs from its own data dir. The paths are absolute whether or not core
+/// dumps are collected, so that both runs of a seed get the same command line.
+pub fn command_paths(fdbserver_path: &str, cli: &Cli) -> std::io::Result<(PathBuf, PathBuf)> {
+    Ok((
+        absolute_command(fdbserver_path)?,
+        std::path::absolute(cli.test_file())?,
+    ))
+}
+
+/// Resolve the relative paths among the `--fdbserver-arg` values, standalone or after `=`
+///
+/// Simulations collecting core dumps do not run from the current directory, the paths would
+/// otherwise resolve elsewhere. Only values naming an existing file or directory are resolved.
+fn absolute_arg_paths(args: &[String]) -> Vec<String> {
+    let resolve = |value: &str| {
+        let path = Path::new(value);
+        if path.is_relative()
+            && !value.is_empty()
+            && !value.starts_with('-')
+            && path.exists()
+            && let Ok(path) = std::path::absolute(path)
+        {
+            return Some(path.display().to_string());
+        }
+        None
+    };
+    args.iter()
+        .map(|arg| match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with('-') => match resolve(value) {
+                Some(value) => format!("{flag}={value}"),
+                None => arg.clone(),
+            },
+            _ => resolve(arg).unwrap_or_else(|| arg.clone()),
+        })
+        .collect()
+}
+
+/// fdbserver flags set by the seeker itself, which `--fdbserver-arg` may not override
+const RESERVED_FDBSERVER_FLAGS: [&str; 12] = [
+    "-r",
+    "--role",
+    "-f",
+    "--testfile",
+    "-d",
+    "--datadir",
+    "-L",
+    "--logdir",
+    "--trace-format",
+    "--trace_format",
+    "-s",
+    "--seed",
+];
+
+/// fdbserver flags setting buggify, replacing `--buggify` when passed through
+const BUGGIFY_FLAGS: [&str; 2] = ["-b", "--buggify"];
+
+/// Name of the flag of an fdbserver argument, without its value if attached
+///
+/// The value follows a `=` (`--seed=3`, `-s=3`), or directly the letter of a short flag
+/// (`-s3`, `-L/tmp/logs`).
+fn flag_name(arg: &str) -> &str {
+    if arg.starts_with("--") {
+        return arg.split_once('=').map_or(arg, |(name, _)| name);
+    }
+    match arg.char_indices().nth(2) {
+        Some((end, _)) if arg.starts_with('-') => &arg[..end],
+        _ => arg,
+    }
+}
+
+/// Check that the `--fdbserver-arg` values do not override a flag set by the seeker
+pub fn check_fdbserver_args(args: &[String]) -> Result<(), SeekerError> {
+    match args
+        .iter()
+        .find(|arg| RESERVED_FDBSERVER_FLAGS.contains(&flag_name(arg)))
+    {
+        Some(arg) => Err(SeekerError::Config(format!(
+            "--fdbserver-arg `{arg}` is reserved, the seeker sets it for every simulation"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Assemble the fdbserver command line simulating `seed` in `data_dir`
+///
+/// The `--fdbserver-arg` values, then `extra_args`, are passed before the seed.
+pub fn fdbserver_argv(
+    fdbserver_path: &Path,
+    test_file: &Path,
+    data_dir: &Path,
+    seed: u32,
+    cli: &Cli,
+    extra_args: &[String],
+) -> Vec<String> {
+    let mut argv = vec![
+        fdbserver_path.display().to_string(),
+        "-r".to_string(),
+        "simulation".to_string(),
+    ];
+    if !cli
+        .fdbserver_args
+        .iter()
+        .any(|arg| BUGGIFY_FLAGS.contains(&flag_name(arg)))
+    {
+        argv.push("-b".to_string());
+        argv.push(cli.buggify.as_arg().to_string());
+    }
+    argv.extend([
+        "--trace-format".to_string(),
+        "json".to_string(),
+        "-f".to_string(),
+        test_file.display().to_string(),
+        "-d".to_string(),
+        data_dir.join("simfdb").display().to_string(),
+        "-L".to_string(),
+        data_dir.join("logs").display().to_string(),
+    ]);
+
+    if let Some(simulation_count) = cli.simulation_count {
+        argv.push(cli.simulation_count_flag.clone());
+        argv.push(simulation_count.to_string());
+    }
+
+    argv.extend(absolute_arg_paths(&cli.fdbserver_args));
+    argv.extend_from_slice(extra_args);
+
+    argv.push("-s".to_string());
+    argv.push(seed.to_string());
+
+    argv
+}
+
+/// Shell variable holding the data directory in reproduce scripts
+const SCRIPT_DATA_DIR: &str = "$DATA_DIR";
+
+/// Markers of the names of options which may hold secrets, never displayed
+pub const SENSITIVE_ENV_MARKERS: [&str; 5] = ["TOKEN", "SECRET", "PASSWORD", "KEY", "WEBHOOK"];
+
+/// Prefix of the environment variables read by fdbserver, exported by the reproduce scripts
+const FDBSERVER_ENV_PREFIX: &str = "FDB_";
+
+/// Environment variables of the dynamic loader affecting fdbserver, exported by the reproduce
+/// scripts
+const LOADER_ENV_VARS: [&str; 2] = ["LD_LIBRARY_PATH", "LD_PRELOAD"];
+
+/// Variables of `vars` exported by a reproduce script
+///
+/// Only the variables consumed by fdbserver and the `extra` ones named by `--reproduce-env` are
+/// kept: the rest of the environment of a CI job holds credentials, and paths such as `PATH` or
+/// `HOME` which would not fit the machine reproducing the seed.
+fn reproduce_env(
+    vars: impl Iterator<Item = (String, String)>,
+    extra: &[String],
+) -> Vec<(String, String)> {
+    let mut vars: Vec<_> = vars
+        .filter(|(name, _)| {
+            name.starts_with(FDBSERVER_ENV_PREFIX)
+                || LOADER_ENV_VARS.contains(&name.as_str())
+                || extra.contains(name)
+        })
+        .filter(|(name, _)| {
+            !name.is_empty()
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+        .collect();
+    vars.sort();
+    vars
+}
+
+/// Generate a standalone shell script running fdbserver on `seed` as the simulation did
+///
+/// The script recreates the data and logs directories under `$DATA_DIR` (a fresh temporary
+/// directory by default) and exports the environment variables consumed by fdbserver, see
+/// [`reproduce_env`].
+pub fn reproduce_script(fdbserver_path: &str, seed: u32, cli: &Cli) -> Result<String, SeekerError> {
+    let command = absolute_command(fdbserver_path)?;
+    let test_file = std::path::absolute(cli.test_file())?;
+    let argv = fdbserver_argv(
+        &command,
+        &test_file,
+        Path::new(SCRIPT_DATA_DIR),
+        seed,
+        cli,
+        &[],
+    );
+
+    let mut script = format!(
+        "#!/bin/sh\n# Reproduce seed {seed}, generated by {} v{}\nset -e\n\n",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    );
+
+    let vars = std::env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)));
+    for (name, value) in reproduce_env(vars, &cli.reproduce_env) {
+        script.push_str(&format!("export {name}={}\n", shell_quote(&value)));
+    }
+
+    script.push_str(&format!(
+        "\nDATA_DIR=\"${{DATA_DIR:-$(mktemp -d)}}\"\nmkdir -p \"{SCRIPT_DATA_DIR}/simfdb\" \"{SCRIPT_DATA_DIR}/logs\"\necho \"Data and logs are written to {SCRIPT_DATA_DIR}\"\n\nexec"
+    ));
+    for arg in argv {
+        script.push(' ');
+        if arg.starts_with(SCRIPT_DATA_DIR) {
+            script.push_str(&format!("\"{arg}\""));
+        } else {
+            script.push_str(&shell_quote(&arg));
+        }
+    }
+    script.push('\n');
+
+    Ok(script)
+}
+
+/// Quote `value` as a single shell word
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Allow simulations to write core dumps by raising the core file size limit
+///
+/// `subprocess` has no `pre_exec` hook, so the soft limit of the current process is raised
+/// to its hard limit instead; every spawned fdbserver inherits it.
+pub fn enable_core_dumps() -> std::io::Result<()> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: `limit` is a valid, writable rlimit struct
+    if unsafe { libc::getrlimit(libc::RLIMIT_CORE, &mut limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    limit.rlim_cur = limit.rlim_max;
+    // SAFETY: `limit` is a valid rlimit struct
+    if unsafe { libc::setrlimit(libc::RLIMIT_CORE, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Resolve the fdbserver command to an absolute path, leaving bare command names to the `PATH` lookup
+fn absolute_command(command: &str) -> std::io::Result<PathBuf> {
+    if command.contains(std::path::MAIN_SEPARATOR) {
+        std::path::absolute(command)
+    } else {
+        Ok(PathBuf::from(command))
+    }
+}
+
+/// Look for a `core` or `core.<suffix>` file written in the simulation working directory
+fn find_core_dump(dir: &Path) -> std::io::Result<Option<PathBuf>> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if entry.file_type()?.is_file() && (name == "core" || name.starts_with("core.")) {
+            return Ok(Some(entry.path()));
+        }
+    }
+    Ok(None)
+}
+
+/// Describe how the simulations of the same seed on two fdbserver binaries diverged
+///
+/// Returns `None` when both exited the same way, or when any of them timed out
+/// since a timeout cannot be compared with a finished run.
+pub fn describe_divergence(
+    fdbserver_path_a: &str,
+    simulation_a: &Simulation,
+    fdbserver_path_b: &str,
+    simulation_b: &Simulation,
+) -> Option<String> {
+    let (Some(exit_status_a), Some(exit_status_b)) =
+        (simulation_a.exit_status, simulation_b.exit_status)
+    else {
+        return None;
+    };
+
+    if exit_status_a == exit_status_b {
+        return None;
+    }
+
+    Some(format!(
+        "`{fdbserver_path_a}` {} while `{fdbserver_path_b}` {}",
+        describe_exit_status(exit_status_a),
+        describe_exit_status(exit_status_b)
+    ))
+}
+
+/// Name of the usual signals ending a crashing or killed fdbserver
+fn signal_name(signal: i32) -> Option<&'static str> {
+    Some(match signal {
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGINT => "SIGINT",
+        libc::SIGQUIT => "SIGQUIT",
+        libc::SIGILL => "SIGILL",
+        libc::SIGTRAP => "SIGTRAP",
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGPIPE => "SIGPIPE",
+        libc::SIGALRM => "SIGALRM",
+        libc::SIGTERM => "SIGTERM",
+        _ => return None,
+    })
+}
+
+/// Human-readable description of how fdbserver exited, e.g. `killed by signal 6 (SIGABRT)`
+///
+/// Exit codes above 128 are how shells report a child killed by a signal, they are annotated with
+/// that signal in case fdbserver was run through a wrapper script.
+pub fn describe_exit_status(exit_status: ExitStatus) -> String {
+    match exit_status {
+        ExitStatus::Exited(code) => match (code > 128)
+            .then(|| signal_name(code as i32 - 128))
+            .flatten()
+        {
+            Some(name) => format!("exited with code {code} ({name})"),
+            None => format!("exited with code {code}"),
+        },
+        ExitStatus::Signaled(signal) => match signal_name(signal.into()) {
+            Some(name) => format!("killed by signal {signal} ({name})"),
+            None => format!("killed by signal {signal}"),
+        },
+        exit_status => format!("exited with {exit_status:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_simulate_large_output() {
+        // More than a pipe buffer on both streams, the fake fdbserver would block if not drained
+        let dir = tempfile::tempdir().unwrap();
+        let fake_fdbserver = dir.path().join("fdbserver");
+        std::fs::write(
+            &fake_fdbserver,
+            "#!/bin/sh\nhead -c 1200000 /dev/zero | tr '\\0' o\nhead -c 1200000 /dev/zero | tr '\\0' e >&2\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&fake_fdbserver, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let cli = Cli::try_parse_from([
+            "seed-seeker",
+            "--fdbserver-path",
+            fake_fdbserver.to_str().unwrap(),
+            "--test-file",
+            "test.toml",
+            "--timeout-secs",
+            "30",
+        ])
+        .unwrap();
+        let simulation = simulate(
+            &cli.fdbserver_path,
+            1,
+            &cli,
+            Workspace::prepare().unwrap(),
+            &[],
+        )
+        .unwrap();
+
+        assert!(!simulation.timed_out());
+        assert!(!simulation.is_faulty());
+        assert_eq!(simulation.stdout.unwrap().len(), 1_200_000);
+        assert_eq!(simulation.stderr.unwrap().len(), 1_200_000);
+    }
+
+    #[test]
+    fn test_simulate_inherited_pipe() {
+        // A background process keeping the output pipe open must not block the worker
+        let dir = tempfile::tempdir().unwrap();
+        let fake_fdbserver = dir.path().join("fdbserver");
+        std::fs::write(&fake_fdbserver, "#!/bin/sh\necho started\nsleep 30 &\n").unwrap();
+        std::fs::set_permissions(&fake_fdbserver, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let cli = Cli::try_parse_from([
+            "seed-seeker",
+            "--fdbserver-path",
+            fake_fdbserver.to_str().unwrap(),
+            "--test-file",
+            "test.toml",
+        ])
+        .unwrap();
+        let started = Instant::now();
+        let simulation = simulate(
+            &cli.fdbserver_path,
+            1,
+            &cli,
+            Workspace::prepare().unwrap(),
+            &[],
+        )
+        .unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(20));
+        assert!(!simulation.is_faulty());
+        assert_eq!(simulation.stdout, None);
+    }
+
+    #[test]
+    fn test_simulate_spawn_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing_fdbserver = dir.path().join("fdbserver");
+
+        let cli = Cli::try_parse_from([
+            "seed-seeker",
+            "--fdbserver-path",
+            missing_fdbserver.to_str().unwrap(),
+            "--test-file",
+            "test.toml",
+        ])
+        .unwrap();
+        let result = simulate(
+            &cli.fdbserver_path,
+            1,
+            &cli,
+            Workspace::prepare().unwrap(),
+            &[],
+        );
+
+        assert!(matches!(result, Err(SeekerError::Spawn(_))));
+    }
+
+    #[test]
+    fn test_fdbserver_args() {
+        let cli = Cli::try_parse_from([
+            "seed-seeker",
+            "--test-file",
+            "test.toml",
+            "--fdbserver-arg",
+            "--knob_something=1",
+            "--fdbserver-arg=-b",
+            "--fdbserver-arg",
+            "off",
+        ])
+        .unwrap();
+        check_fdbserver_args(&cli.fdbserver_args).unwrap();
+        let argv = fdbserver_argv(
+            Path::new("fdbserver"),
+            Path::new("test.toml"),
+            Path::new("/data"),
+            7,
+            &cli,
+            &[],
+        );
+
+        // The passthrough buggify replaces the default one
+        assert_eq!(argv.iter().filter(|arg| *arg == "-b").count(), 1);
+        assert!(argv.ends_with(&[
+            "--knob_something=1".to_string(),
+            "-b".to_string(),
+            "off".to_string(),
+            "-s".to_string(),
+            "7".to_string(),
+        ]));
+
+        for reserved in [
+            "--seed=3",
+            "-L",
+            "-L/tmp/x",
+            "-s3",
+            "-s=3",
+            "-fpath",
+            "--testfile=other.toml",
+            "--trace_format=xml",
+        ] {
+            assert!(
+                matches!(
+                    check_fdbserver_args(&[reserved.to_string()]),
+                    Err(SeekerError::Config(_))
+                ),
+                "{reserved}"
+            );
+        }
+        check_fdbserver_args(&["--seedless".to_string(), "-C".to_string()]).unwrap();
+    }
+
+    #[test]
+    fn test_absolute_arg_paths() {
+        // Tests run from the package root, which holds `src`
+        let src = std::path::absolute("src").unwrap().display().to_string();
+        let args = ["--knob_x=1", "--trace_dir=src", "src", "-b", "not_a_file"].map(String::from);
+        assert_eq!(
+            absolute_arg_paths(&args),
+            [
+                "--knob_x=1".to_string(),
+                format!("--trace_dir={src}"),
+                src.clone(),
+                "-b".to_string(),
+                "not_a_file".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_describe_exit_status() {
+        assert_eq!(
+            describe_exit_status(ExitStatus::Exited(1)),
+            "exited with code 1"
+        );
+        assert_eq!(
+            describe_exit_status(ExitStatus::Exited(134)),
+            "exited with code 134 (SIGABRT)"
+        );
+        assert_eq!(
+            describe_exit_status(ExitStatus::Signaled(11)),
+            "killed by signal 11 (SIGSEGV)"
+        );
+    }
+
+    #[test]
+    fn test_reproduce_env() {
+        let vars = [
+            ("CI_JOB_JWT", "eyJhbGciOiJSUzI1NiJ9"),
+            ("REGISTRY_AUTH", "user:pass"),
+            ("DATABASE_URL", "postgres://user:pass@db/ci"),
+            ("PATH", "/builds/runner/bin:/usr/bin"),
+            ("HOME", "/home/runner"),
+            ("FDB_NETWORK_OPTION_TRACE_ENABLE", "/tmp/traces"),
+            ("LD_LIBRARY_PATH", "/opt/fdb/lib"),
+            ("SIM_WORKERS", "8"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        let exported = reproduce_env(vars.into_iter(), &["SIM_WORKERS".to_string()]);
+        let names: Vec<_> = exported.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "FDB_NETWORK_OPTION_TRACE_ENABLE",
+                "LD_LIBRARY_PATH",
+                "SIM_WORKERS"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_buggify() {
+        let argv = |buggify: &str| {
+            let cli = Cli::try_parse_from([
+                "seed-seeker",
+                "--test-file",
+                "test.toml",
+                "--buggify",
+                buggify,
+            ])
+            .unwrap();
+            fdbserver_argv(
+                Path::new("fdbserver"),
+                Path::new("test.toml"),
+                Path::new("/data"),
+                7,
+                &cli,
+                &[],
+            )
+        };
+
+        for buggify in ["on", "off", "random"] {
+            let argv = argv(buggify);
+            let position = argv.iter().position(|arg| arg == "-b").unwrap();
+            assert_eq!(argv[position + 1], buggify);
+        }
+    }
+}
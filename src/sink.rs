@@ -0,0 +1,345 @@
+use crate::reporter::ReportedIssue;
+use crate::summary::SeedOutcome;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Receives the outcome of every checked seed as soon as it reaches the coordinator
+///
+/// Each seed is recorded once per test file, `test_file` naming it when several are run.
+pub trait OutcomeSink {
+    fn record(
+        &mut self,
+        seed: u32,
+        test_file: Option<&str>,
+        outcome: &SeedOutcome,
+        duration: Duration,
+    );
+}
+
+/// Push counters and timers to a statsd endpoint over UDP
+///
+/// Sending is best-effort: failures are logged as warnings and never interrupt the run.
+pub struct StatsdSink {
+    socket: UdpSocket,
+    prefix: String,
+}
+
+impl StatsdSink {
+    pub fn new(addr: &str, prefix: &str) -> std::io::Result<Self> {
+        let addr = addr.to_socket_addrs()?.next().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("statsd address {addr} does not resolve"),
+            )
+        })?;
+        let socket = if addr.is_ipv4() {
+            UdpSocket::bind("0.0.0.0:0")?
+        } else {
+            UdpSocket::bind("[::]:0")?
+        };
+        socket.connect(addr)?;
+
+        Ok(Self {
+            socket,
+            prefix: prefix.to_string(),
+        })
+    }
+
+    fn send(&self, metric: &str, value: u128, kind: &str) {
+        let line = format!("{}.{metric}:{value}|{kind}", self.prefix);
+        if let Err(e) = self.socket.send(line.as_bytes()) {
+            warn!(error = ?e, metric, "Failed to send metric to statsd");
+        }
+    }
+}
+
+impl OutcomeSink for StatsdSink {
+    fn record(
+        &mut self,
+        _seed: u32,
+        _test_file: Option<&str>,
+        outcome: &SeedOutcome,
+        duration: Duration,
+    ) {
+        self.send("seeds.completed", 1, "c");
+        match outcome {
+            SeedOutcome::Ok => self.send("seeds.ok", 1, "c"),
+            SeedOutcome::Faulty { .. } => self.send("seeds.faulty", 1, "c"),
+            SeedOutcome::TimedOut => self.send("seeds.timed_out", 1, "c"),
+            SeedOutcome::Suspicious => self.send("seeds.suspicious", 1, "c"),
+            SeedOutcome::Error => self.send("seeds.errors", 1, "c"),
+        }
+        self.send("seed.duration", duration.as_millis(), "ms");
+    }
+}
+
+/// Write the outcome of every seed to its own `<seed>.json` file in a directory
+///
+/// Each file can be archived independently, so CI collecting partial or sharded runs can glob them.
+/// When several test files are run, each outcome goes to `<seed>_<test file>.json` instead.
+pub struct ResultDirSink {
+    dir: PathBuf,
+}
+
+impl ResultDirSink {
+    pub fn new(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+}
+
+impl OutcomeSink for ResultDirSink {
+    fn record(
+        &mut self,
+        seed: u32,
+        test_file: Option<&str>,
+        outcome: &SeedOutcome,
+        duration: Duration,
+    ) {
+        let issue = match outcome {
+            SeedOutcome::Faulty { issue, .. } => issue.as_ref(),
+            _ => None,
+        };
+        let result = serde_json::json!({
+            "seed": seed,
+            "test_file": test_file,
+            "outcome": outcome.name(),
+            "issue_created": matches!(issue, Some(ReportedIssue::Created(_))),
+            "issue_url": issue.map(ReportedIssue::url),
+            "duration_secs": duration.as_secs_f64(),
+        });
+        let path = self.dir.join(match test_file {
+            Some(test_file) => format!("{seed}_{test_file}.json"),
+            None => format!("{seed}.json"),
+        });
+        if let Err(e) = std::fs::write(&path, result.to_string()) {
+            warn!(seed, error = ?e, path = %path.display(), "Failed to write seed result file");
+        }
+    }
+}
+
+/// Counters of the run exposed to Prometheus, shared by the coordinator, the workers and the server
+#[derive(Debug, Default)]
+pub struct Metrics {
+    seeds_total: AtomicU64,
+    seeds_ok: AtomicU64,
+    seeds_faulty: AtomicU64,
+    seeds_timed_out: AtomicU64,
+    seeds_suspicious: AtomicU64,
+    seeds_errors: AtomicU64,
+    inflight: AtomicU64,
+}
+
+impl Metrics {
+    /// Count a simulation starting on a worker
+    pub fn start_simulation(&self) {
+        self.inflight.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a simulation done on a worker
+    pub fn end_simulation(&self) {
+        self.inflight.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Render the metrics in the Prometheus text exposition format
+    fn render(&self) -> String {
+        let mut text = String::new();
+        for (name, kind, help, value) in [
+            ("seeds_total", "counter", "Seeds checked", &self.seeds_total),
+            ("seeds_ok", "counter", "Seeds passing", &self.seeds_ok),
+            (
+                "seeds_faulty",
+                "counter",
+                "Faulty seeds",
+                &self.seeds_faulty,
+            ),
+            (
+                "seeds_timed_out",
+                "counter",
+                "Timed-out seeds",
+                &self.seeds_timed_out,
+            ),
+            (
+                "seeds_suspicious",
+                "counter",
+                "Suspicious seeds",
+                &self.seeds_suspicious,
+            ),
+            (
+                "seeds_errors",
+                "counter",
+                "Seeds which could not be run",
+                &self.seeds_errors,
+            ),
+            (
+                "simulations_inflight",
+                "gauge",
+                "Simulations currently running",
+                &self.inflight,
+            ),
+        ] {
+            text.push_str(&format!(
+                "# HELP seed_seeker_{name} {help}\n# TYPE seed_seeker_{name} {kind}\nseed_seeker_{name} {}\n",
+                value.load(Ordering::Relaxed)
+            ));
+        }
+        text
+    }
+}
+
+/// Serve the metrics of the run in the Prometheus text format over HTTP
+///
+/// Every request gets the metrics whatever its path, from a background thread living as long as
+/// the process.
+pub struct PrometheusSink {
+    metrics: Arc<Metrics>,
+}
+
+impl PrometheusSink {
+    pub fn new(addr: &str) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        info!(addr = %listener.local_addr()?, "Serving Prometheus metrics");
+        let metrics = Arc::new(Metrics::default());
+        let served = Arc::clone(&metrics);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let result = stream.and_then(|stream| serve_metrics(stream, &served));
+                if let Err(e) = result {
+                    warn!(error = ?e, "Failed to serve Prometheus metrics");
+                }
+            }
+        });
+
+        Ok(Self { metrics })
+    }
+
+    /// Metrics updated by the workers as simulations start and end
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.metrics)
+    }
+}
+
+fn serve_metrics(mut stream: TcpStream, metrics: &Metrics) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    // The request itself does not matter, only read it so that the client sees a clean close
+    let mut request = [0; 1024];
+    let _ = stream.read(&mut request)?;
+    let body = metrics.render();
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+impl OutcomeSink for PrometheusSink {
+    fn record(
+        &mut self,
+        _seed: u32,
+        _test_file: Option<&str>,
+        outcome: &SeedOutcome,
+        _duration: Duration,
+    ) {
+        let metrics = &self.metrics;
+        metrics.seeds_total.fetch_add(1, Ordering::Relaxed);
+        let counter = match outcome {
+            SeedOutcome::Ok => &metrics.seeds_ok,
+            SeedOutcome::Faulty { .. } => &metrics.seeds_faulty,
+            SeedOutcome::TimedOut => &metrics.seeds_timed_out,
+            SeedOutcome::Suspicious => &metrics.seeds_suspicious,
+            SeedOutcome::Error => &metrics.seeds_errors,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statsd_lines() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut sink = StatsdSink::new(&server.local_addr().unwrap().to_string(), "ci").unwrap();
+
+        sink.record(7, None, &SeedOutcome::TimedOut, Duration::from_millis(1500));
+
+        let mut lines = Vec::new();
+        let mut buffer = [0; 512];
+        for _ in 0..3 {
+            let len = server.recv(&mut buffer).unwrap();
+            lines.push(String::from_utf8_lossy(&buffer[..len]).into_owned());
+        }
+        assert_eq!(
+            lines,
+            [
+                "ci.seeds.completed:1|c",
+                "ci.seeds.timed_out:1|c",
+                "ci.seed.duration:1500|ms"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_result_dir_file_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut sink = ResultDirSink::new(dir.path().join("results")).unwrap();
+        let faulty = SeedOutcome::Faulty {
+            issue: Some(ReportedIssue::Commented(
+                "https://gitlab/issues/3".to_string(),
+            )),
+            filtered_output: None,
+        };
+
+        sink.record(12, None, &SeedOutcome::Ok, Duration::from_secs(2));
+        sink.record(12, Some("CycleTest.toml"), &faulty, Duration::from_secs(3));
+
+        let result: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(dir.path().join("results/12.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(result["outcome"], "ok");
+        assert_eq!(result["test_file"], serde_json::Value::Null);
+        assert_eq!(result["issue_url"], serde_json::Value::Null);
+
+        let result: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(dir.path().join("results/12_CycleTest.toml.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(result["outcome"], "faulty");
+        assert_eq!(result["test_file"], "CycleTest.toml");
+        assert_eq!(result["issue_created"], false);
+        assert_eq!(result["issue_url"], "https://gitlab/issues/3");
+    }
+
+    #[test]
+    fn test_prometheus_exposition() {
+        let mut sink = PrometheusSink::new("127.0.0.1:0").unwrap();
+        let metrics = sink.metrics();
+        metrics.start_simulation();
+        metrics.start_simulation();
+        metrics.end_simulation();
+        sink.record(1, None, &SeedOutcome::Ok, Duration::ZERO);
+        sink.record(2, None, &SeedOutcome::Error, Duration::ZERO);
+
+        let text = metrics.render();
+        assert!(text.contains(
+            "# HELP seed_seeker_seeds_total Seeds checked\n# TYPE seed_seeker_seeds_total counter\nseed_seeker_seeds_total 2\n"
+        ));
+        assert!(text.contains("\nseed_seeker_seeds_ok 1\n"));
+        assert!(text.contains("\nseed_seeker_seeds_errors 1\n"));
+        assert!(text.contains("\nseed_seeker_seeds_faulty 0\n"));
+        assert!(text.contains(
+            "# TYPE seed_seeker_simulations_inflight gauge\nseed_seeker_simulations_inflight 1\n"
+        ));
+    }
+}
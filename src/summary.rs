@@ -0,0 +1,495 @@
+use crate::reporter::ReportedIssue;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use tracing::info;
+
+/// Outcome of checking a single seed, sent back by the workers to the coordinator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeedOutcome {
+    /// No error found
+    Ok,
+    /// The simulation failed, `issue` is set when it was reported in an issue
+    ///
+    /// `filtered_output` holds the trace events embedded in the report, unless the seed was
+    /// already reported during the run.
+    Faulty {
+        issue: Option<ReportedIssue>,
+        filtered_output: Option<String>,
+    },
+    /// The simulation was terminated after reaching the timeout
+    TimedOut,
+    /// The simulation exited with a failure status but its traces hold no faulty event, or the
+    /// confirmation re-runs did not reproduce the failure
+    Suspicious,
+    /// The seed could not be checked
+    Error,
+}
+
+impl SeedOutcome {
+    /// Short name of the outcome used in machine-readable outputs
+    pub fn name(&self) -> &'static str {
+        match self {
+            SeedOutcome::Ok => "ok",
+            SeedOutcome::Faulty { .. } => "faulty",
+            SeedOutcome::TimedOut => "timed_out",
+            SeedOutcome::Suspicious => "suspicious",
+            SeedOutcome::Error => "error",
+        }
+    }
+}
+
+/// How the checked seeds went, which sets the exit code of the process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    /// No faulty or timed-out seed, and every seed could be checked
+    Clean,
+    /// At least one faulty seed was found
+    Faulty,
+    /// Some seeds timed out, none was faulty
+    TimedOut,
+    /// Some seeds could not be checked, none was faulty
+    Errors,
+}
+
+impl RunStatus {
+    /// Exit code of the process for this status
+    pub fn exit_code(self) -> i32 {
+        match self {
+            RunStatus::Clean => 0,
+            RunStatus::Faulty => 2,
+            RunStatus::TimedOut => 3,
+            RunStatus::Errors => 4,
+        }
+    }
+}
+
+/// Seed of the lists of the summary, with the test file it failed on when several are run
+///
+/// Serialized as a bare seed without a test file, so that the summaries of single test file runs
+/// keep their format.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(from = "SeedEntryRepr", into = "SeedEntryRepr")]
+pub struct SeedEntry {
+    pub seed: u32,
+    pub test_file: Option<String>,
+}
+
+impl SeedEntry {
+    pub fn new(seed: u32, test_file: Option<&str>) -> Self {
+        Self {
+            seed,
+            test_file: test_file.map(str::to_string),
+        }
+    }
+}
+
+impl fmt::Display for SeedEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.test_file {
+            Some(test_file) => write!(f, "{} ({test_file})", self.seed),
+            None => write!(f, "{}", self.seed),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SeedEntryRepr {
+    Seed(u32),
+    InTestFile { seed: u32, test_file: String },
+}
+
+impl From<SeedEntryRepr> for SeedEntry {
+    fn from(repr: SeedEntryRepr) -> Self {
+        match repr {
+            SeedEntryRepr::Seed(seed) => SeedEntry {
+                seed,
+                test_file: None,
+            },
+            SeedEntryRepr::InTestFile { seed, test_file } => SeedEntry {
+                seed,
+                test_file: Some(test_file),
+            },
+        }
+    }
+}
+
+impl From<SeedEntry> for SeedEntryRepr {
+    fn from(entry: SeedEntry) -> Self {
+        match entry.test_file {
+            Some(test_file) => SeedEntryRepr::InTestFile {
+                seed: entry.seed,
+                test_file,
+            },
+            None => SeedEntryRepr::Seed(entry.seed),
+        }
+    }
+}
+
+/// Render `seeds` as a comma-separated list
+fn list(seeds: &[SeedEntry]) -> String {
+    seeds
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Order of the seed lists of the summary
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeedOrder {
+    /// Ascending seed values
+    Asc,
+    /// Descending seed values
+    Desc,
+    /// Order in which the seeds completed
+    Completion,
+}
+
+/// Destination of the summary posted at the end of a run
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SummaryTarget {
+    /// A new issue in the GitLab project
+    Issue,
+    /// A note on a GitLab merge request
+    MrNote,
+    /// A Slack incoming webhook
+    Slack,
+}
+
+/// Accounting of the seed outcomes of a run
+///
+/// Serialized as the JSON summary of `--summary-json`, read back by `merge-summaries`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Summary {
+    /// Number of seeds checked, whatever their outcome, once per test file
+    pub checked_seeds: usize,
+    /// Seeds for which a faulty simulation was found
+    pub faulty_seeds: Vec<SeedEntry>,
+    /// Seeds whose simulation timed out
+    pub timed_out_seeds: Vec<SeedEntry>,
+    /// Number of issues successfully created
+    pub issues_created: usize,
+    /// URLs of the created issues
+    pub issue_urls: Vec<String>,
+    /// URLs of the open issues commented on instead of filing new ones (`--dedup-issues`)
+    pub commented_issue_urls: Vec<String>,
+    /// Seeds that exited with a failure status without any faulty trace event
+    pub suspicious_seeds: Vec<SeedEntry>,
+    /// Number of seeds that could not be checked
+    pub errors: usize,
+    /// Category of the test file if set
+    pub test_category: Option<String>,
+    /// Commit ID of the tested build if set
+    pub commit_id: Option<String>,
+    /// Test file the seeds were run with
+    pub test_file: Option<String>,
+    /// Whether the run stopped early because too many faulty seeds were found
+    pub aborted: bool,
+    /// Wall-clock duration of the run, summed over the runs of a merged summary
+    pub duration_secs: f64,
+}
+
+impl Summary {
+    /// Account for the outcome of `seed` on a test file, named when several are run
+    pub fn record(&mut self, seed: u32, test_file: Option<&str>, outcome: &SeedOutcome) {
+        self.checked_seeds += 1;
+        let entry = SeedEntry::new(seed, test_file);
+        match outcome {
+            SeedOutcome::Ok => {}
+            SeedOutcome::Faulty { issue, .. } => {
+                self.faulty_seeds.push(entry);
+                if let Some(issue) = issue {
+                    self.add_issue(issue);
+                }
+            }
+            SeedOutcome::TimedOut => self.timed_out_seeds.push(entry),
+            SeedOutcome::Suspicious => self.suspicious_seeds.push(entry),
+            SeedOutcome::Error => self.errors += 1,
+        }
+    }
+
+    /// Status of the run, a faulty seed taking precedence over the seeds which could not be
+    /// checked, themselves over timeouts
+    pub fn status(&self) -> RunStatus {
+        if !self.faulty_seeds.is_empty() {
+            RunStatus::Faulty
+        } else if self.errors > 0 {
+            RunStatus::Errors
+        } else if !self.timed_out_seeds.is_empty() {
+            RunStatus::TimedOut
+        } else {
+            RunStatus::Clean
+        }
+    }
+
+    /// Account for the issue a faulty seed was reported in, only new issues counting as created
+    pub fn add_issue(&mut self, issue: &ReportedIssue) {
+        match issue {
+            ReportedIssue::Created(url) => {
+                self.issues_created += 1;
+                self.issue_urls.push(url.clone());
+            }
+            ReportedIssue::Commented(url) => {
+                if !self.commented_issue_urls.contains(url) {
+                    self.commented_issue_urls.push(url.clone());
+                }
+            }
+        }
+    }
+
+    /// Aggregate the summary of another run, e.g. another shard of the same run
+    ///
+    /// Counts and durations are added up while the seed and issue lists are merged without
+    /// duplicates, the created issues being counted from the merged list.
+    pub fn merge(&mut self, other: Summary) {
+        fn union<T: Clone + Eq + std::hash::Hash>(items: &mut Vec<T>, others: Vec<T>) {
+            let mut known: HashSet<T> = items.iter().cloned().collect();
+            items.extend(others.into_iter().filter(|item| known.insert(item.clone())));
+        }
+
+        self.checked_seeds += other.checked_seeds;
+        union(&mut self.faulty_seeds, other.faulty_seeds);
+        union(&mut self.timed_out_seeds, other.timed_out_seeds);
+        union(&mut self.suspicious_seeds, other.suspicious_seeds);
+        union(&mut self.issue_urls, other.issue_urls);
+        self.issues_created = self.issue_urls.len();
+        union(&mut self.commented_issue_urls, other.commented_issue_urls);
+        self.errors += other.errors;
+        if self.test_category != other.test_category {
+            self.test_category = None;
+        }
+        if self.commit_id != other.commit_id {
+            self.commit_id = None;
+        }
+        if self.test_file != other.test_file {
+            self.test_file = None;
+        }
+        self.aborted |= other.aborted;
+        self.duration_secs += other.duration_secs;
+    }
+
+    /// Sort the faulty and timed-out seeds for a stable, diffable output
+    pub fn sort(&mut self, order: SeedOrder) {
+        for seeds in [
+            &mut self.faulty_seeds,
+            &mut self.timed_out_seeds,
+            &mut self.suspicious_seeds,
+        ] {
+            match order {
+                SeedOrder::Asc => seeds.sort_unstable(),
+                SeedOrder::Desc => seeds.sort_unstable_by(|a, b| b.cmp(a)),
+                SeedOrder::Completion => {}
+            }
+        }
+    }
+
+    pub fn log(&self) {
+        info!(
+            checked_seeds = self.checked_seeds,
+            faulty_seeds = self.faulty_seeds.len(),
+            timed_out_seeds = self.timed_out_seeds.len(),
+            suspicious_seeds = self.suspicious_seeds.len(),
+            issues_created = self.issues_created,
+            issues_commented = self.commented_issue_urls.len(),
+            errors = self.errors,
+            test_category = ?self.test_category,
+            aborted = self.aborted,
+            "Run finished"
+        );
+        if !self.faulty_seeds.is_empty() {
+            info!(
+                faulty_seeds = list(&self.faulty_seeds),
+                "Faulty seeds found"
+            );
+        }
+        if !self.timed_out_seeds.is_empty() {
+            info!(
+                timed_out_seeds = list(&self.timed_out_seeds),
+                "Timed-out seeds found"
+            );
+        }
+        if !self.suspicious_seeds.is_empty() {
+            info!(
+                suspicious_seeds = list(&self.suspicious_seeds),
+                "Seeds exiting with a failure status without faulty trace events found"
+            );
+        }
+    }
+
+    /// Render the summary as markdown, to be posted at the end of the run
+    pub fn render_markdown(&self) -> String {
+        let mut markdown = String::from("### Seed Seeker run summary\n\n");
+        if let Some(commit_id) = &self.commit_id {
+            markdown.push_str(&format!("- Commit ID: {commit_id}\n"));
+        }
+        if let Some(test_file) = &self.test_file {
+            markdown.push_str(&format!("- Test file: {test_file}\n"));
+        }
+        if let Some(test_category) = &self.test_category {
+            markdown.push_str(&format!("- Test category: {test_category}\n"));
+        }
+        markdown.push_str(&format!("- Checked seeds: {}\n", self.checked_seeds));
+        markdown.push_str(&format!("- Faulty seeds: {}", self.faulty_seeds.len()));
+        if !self.faulty_seeds.is_empty() {
+            markdown.push_str(&format!(" ({})", list(&self.faulty_seeds)));
+        }
+        markdown.push_str(&format!(
+            "\n- Timed-out seeds: {}",
+            self.timed_out_seeds.len()
+        ));
+        if !self.timed_out_seeds.is_empty() {
+            markdown.push_str(&format!(" ({})", list(&self.timed_out_seeds)));
+        }
+        if !self.suspicious_seeds.is_empty() {
+            markdown.push_str(&format!(
+                "\n- Suspicious seeds (failure exit status, no faulty event): {} ({})",
+                self.suspicious_seeds.len(),
+                list(&self.suspicious_seeds)
+            ));
+        }
+        markdown.push_str(&format!("\n- Errors: {}\n", self.errors));
+        markdown.push_str(&format!("- Issues created: {}\n", self.issues_created));
+        if !self.commented_issue_urls.is_empty() {
+            markdown.push_str(&format!(
+                "- Existing issues commented: {}\n",
+                self.commented_issue_urls.len()
+            ));
+        }
+        if self.aborted {
+            markdown.push_str("- :warning: Aborted after too many faulty seeds\n");
+        }
+        if !self.issue_urls.is_empty() {
+            markdown.push_str("\nIssues:\n");
+            for issue_url in &self.issue_urls {
+                markdown.push_str(&format!("- {issue_url}\n"));
+            }
+        }
+        if !self.commented_issue_urls.is_empty() {
+            markdown.push_str("\nCommented issues:\n");
+            for issue_url in &self.commented_issue_urls {
+                markdown.push_str(&format!("- {issue_url}\n"));
+            }
+        }
+        markdown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let mut summary = Summary {
+            checked_seeds: 10,
+            faulty_seeds: vec![SeedEntry::new(1, None), SeedEntry::new(2, Some("a.toml"))],
+            timed_out_seeds: vec![SeedEntry::new(3, None)],
+            issues_created: 1,
+            issue_urls: vec!["https://gitlab.com/issues/1".to_string()],
+            errors: 1,
+            test_category: Some("storage".to_string()),
+            commit_id: Some("9b1fc0a".to_string()),
+            test_file: Some("a.toml".to_string()),
+            duration_secs: 1.5,
+            ..Default::default()
+        };
+        summary.merge(Summary {
+            checked_seeds: 5,
+            faulty_seeds: vec![SeedEntry::new(2, Some("a.toml")), SeedEntry::new(2, None)],
+            timed_out_seeds: vec![SeedEntry::new(3, None), SeedEntry::new(4, None)],
+            suspicious_seeds: vec![SeedEntry::new(5, None)],
+            issues_created: 2,
+            // The same issue is listed by both runs, e.g. when re-running a shard
+            issue_urls: vec![
+                "https://gitlab.com/issues/1".to_string(),
+                "https://gitlab.com/issues/2".to_string(),
+            ],
+            commented_issue_urls: vec!["https://gitlab.com/issues/7".to_string()],
+            errors: 2,
+            test_category: Some("storage".to_string()),
+            commit_id: Some("0c4e2d1".to_string()),
+            test_file: Some("b.toml".to_string()),
+            aborted: true,
+            duration_secs: 2.0,
+        });
+
+        assert_eq!(summary.checked_seeds, 15);
+        assert_eq!(
+            summary.faulty_seeds,
+            [
+                SeedEntry::new(1, None),
+                SeedEntry::new(2, Some("a.toml")),
+                SeedEntry::new(2, None)
+            ]
+        );
+        assert_eq!(
+            summary.timed_out_seeds,
+            [SeedEntry::new(3, None), SeedEntry::new(4, None)]
+        );
+        assert_eq!(summary.suspicious_seeds, [SeedEntry::new(5, None)]);
+        assert_eq!(summary.issues_created, 2);
+        assert_eq!(
+            summary.issue_urls,
+            ["https://gitlab.com/issues/1", "https://gitlab.com/issues/2"]
+        );
+        assert_eq!(
+            summary.commented_issue_urls,
+            ["https://gitlab.com/issues/7"]
+        );
+        assert_eq!(summary.errors, 3);
+        assert_eq!(summary.test_category.as_deref(), Some("storage"));
+        assert_eq!(summary.commit_id, None);
+        assert_eq!(summary.test_file, None);
+        assert!(summary.aborted);
+        assert_eq!(summary.duration_secs, 3.5);
+    }
+
+    #[test]
+    fn test_record_commented_issue() {
+        let mut summary = Summary::default();
+        let faulty = |issue: ReportedIssue| SeedOutcome::Faulty {
+            issue: Some(issue),
+            filtered_output: None,
+        };
+        summary.record(
+            1,
+            None,
+            &faulty(ReportedIssue::Created(
+                "https://gitlab.com/issues/1".to_string(),
+            )),
+        );
+        summary.record(
+            2,
+            None,
+            &faulty(ReportedIssue::Commented(
+                "https://gitlab.com/issues/2".to_string(),
+            )),
+        );
+
+        assert_eq!(summary.issues_created, 1);
+        assert_eq!(summary.issue_urls, ["https://gitlab.com/issues/1"]);
+        assert_eq!(
+            summary.commented_issue_urls,
+            ["https://gitlab.com/issues/2"]
+        );
+        assert!(
+            summary
+                .render_markdown()
+                .contains("- Issues created: 1\n- Existing issues commented: 1\n")
+        );
+    }
+
+    #[test]
+    fn test_seed_entry_json() {
+        let seeds = vec![SeedEntry::new(1, None), SeedEntry::new(2, Some("a.toml"))];
+        let json = serde_json::to_string(&seeds).unwrap();
+        assert_eq!(json, r#"[1,{"seed":2,"test_file":"a.toml"}]"#);
+        assert_eq!(
+            serde_json::from_str::<Vec<SeedEntry>>(&json).unwrap(),
+            seeds
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use crate::error::SeekerError;
+use std::path::{Path, PathBuf};
+
+/// Extensions of the fdbserver test files
+const TEST_FILE_EXTENSIONS: [&str; 2] = ["toml", "txt"];
+
+/// Test file found in a test directory
+#[derive(Debug)]
+pub struct TestFile {
+    pub path: PathBuf,
+    /// Category inferred from the first sub-directory holding the file, if any
+    pub category: Option<String>,
+}
+
+/// Issue label naming the workload of a test file, e.g. `test:CycleTest` for `tests/fast/CycleTest.toml`
+///
+/// Characters other than alphanumerics, `-`, `_` and `.` are replaced with `_`, as commas separate
+/// the labels in GitLab requests.
+pub fn label(path: &Path) -> Option<String> {
+    let name: String = path
+        .file_stem()?
+        .to_string_lossy()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    (!name.is_empty()).then(|| format!("test:{name}"))
+}
+
+/// Walk `dir` recursively and collect its test files, sorted by path
+pub fn discover(dir: &Path) -> Result<Vec<TestFile>, SeekerError> {
+    let mut test_files = Vec::new();
+
+    for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        let is_test_file = entry.file_type().is_file()
+            && entry
+                .path()
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| TEST_FILE_EXTENSIONS.contains(&extension));
+        if !is_test_file {
+            continue;
+        }
+
+        // tests/storage/foo.toml is in the `storage` category, tests/foo.toml has none
+        let relative = entry.path().strip_prefix(dir)?;
+        let category = relative
+            .parent()
+            .and_then(|parent| parent.components().next())
+            .map(|component| component.as_os_str().to_string_lossy().into_owned());
+
+        test_files.push(TestFile {
+            path: entry.into_path(),
+            category,
+        });
+    }
+
+    Ok(test_files)
+}
+
+/// Timeout set by a `# timeout: <SECONDS>` directive in the leading comments of a test file
+pub fn timeout_directive(path: &Path) -> Result<Option<u64>, SeekerError> {
+    let text = std::fs::read_to_string(path)?;
+    parse_timeout_directive(&text)
+        .map_err(|e| SeekerError::Preflight(format!("Test file {}: {e}", path.display())))
+}
+
+fn parse_timeout_directive(text: &str) -> Result<Option<u64>, String> {
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        // Only the comments heading the file are directives, not the ones inside the workloads
+        let Some(comment) = line.strip_prefix('#') else {
+            break;
+        };
+        if let Some(value) = comment.trim().strip_prefix("timeout:") {
+            return match value.trim().parse() {
+                Ok(timeout_secs) if timeout_secs > 0 => Ok(Some(timeout_secs)),
+                _ => Err(format!("invalid timeout directive `{line}`")),
+            };
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timeout_directive() {
+        let text = "# Slow recovery workload\n# timeout: 300\n\ntestClass = \"Rollback\"\n";
+        assert_eq!(parse_timeout_directive(text), Ok(Some(300)));
+        assert_eq!(
+            parse_timeout_directive("testClass = \"Cycle\"\n# timeout: 300\n"),
+            Ok(None)
+        );
+        assert!(parse_timeout_directive("# timeout: soon\n").is_err());
+        assert!(parse_timeout_directive("# timeout: 0\n").is_err());
+    }
+}